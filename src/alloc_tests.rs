@@ -0,0 +1,807 @@
+//! Unit tests that need neither `std` nor a CFB container. These run with
+//! `--no-default-features`, too.
+
+use super::parser::{
+    compress, cp_to_string, decompress, decompressor_error, dir_parser_error, ensure_consumed,
+    parse_project_information, parse_project_information_with, parser_error, ParseState,
+};
+use super::{
+    iter_dir_records, parse_dir, predicted_decompressed_size, DirBuilder, Error, OpenOptions,
+    ParseErrorKind, Reference, Warning,
+};
+
+use alloc::{string::ToString, vec, vec::Vec};
+
+// -------------------------------------------------------------------------
+// Test helpers
+// -------------------------------------------------------------------------
+
+/// Encodes `data` as a `CompressedContainer` using LiteralTokens only.
+pub(super) fn compress_literals(data: &[u8]) -> Vec<u8> {
+    let mut result = vec![0x01];
+    // 3640 literal bytes plus 455 FlagBytes is the largest payload that fits a chunk.
+    for chunk in data.chunks(3640) {
+        let mut payload = Vec::new();
+        for tokens in chunk.chunks(8) {
+            payload.push(0x00);
+            payload.extend_from_slice(tokens);
+        }
+        let header = 0xb000_u16 | (payload.len() - 1) as u16;
+        result.extend_from_slice(&header.to_le_bytes());
+        result.extend(payload);
+    }
+    result
+}
+
+/// Builds a decompressed *dir* stream. Modules are passed as `(name, stream name, text
+/// offset)` tuples.
+pub(super) fn dir_stream(modules: &[(&str, &str, u32)]) -> Vec<u8> {
+    dir_stream_with_references(&[], modules)
+}
+
+/// Same as `dir_stream`, with `references` holding the raw REFERENCE records.
+pub(super) fn dir_stream_with_references(
+    references: &[u8],
+    modules: &[(&str, &str, u32)],
+) -> Vec<u8> {
+    fn record(id: u16, data: &[u8]) -> Vec<u8> {
+        let mut result = id.to_le_bytes().to_vec();
+        result.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        result.extend_from_slice(data);
+        result
+    }
+    fn utf16(s: &str) -> Vec<u8> {
+        s.encode_utf16().flat_map(|c| c.to_le_bytes()).collect()
+    }
+
+    let mut result = [
+        record(0x01, &1_u32.to_le_bytes()),
+        record(0x02, &0x0409_u32.to_le_bytes()),
+        record(0x14, &0x0409_u32.to_le_bytes()),
+        record(0x03, &1252_u16.to_le_bytes()),
+        record(0x04, b"VBAProject"),
+        record(0x05, b""),
+        record(0x40, b""),
+        record(0x06, b""),
+        record(0x3d, b""),
+        record(0x07, &0_u32.to_le_bytes()),
+        record(0x08, &0_u32.to_le_bytes()),
+        b"\x09\x00\x04\x00\x00\x00\x01\x00\x00\x00\x00\x00".to_vec(),
+        references.to_vec(),
+        record(0x0f, &(modules.len() as u16).to_le_bytes()),
+        record(0x13, &0xffff_u16.to_le_bytes()),
+    ]
+    .concat();
+    for (name, stream_name, offset) in modules {
+        result.extend(record(0x19, name.as_bytes()));
+        result.extend(record(0x47, &utf16(name)));
+        result.extend(record(0x1a, stream_name.as_bytes()));
+        result.extend(record(0x32, &utf16(stream_name)));
+        result.extend(record(0x1c, b""));
+        result.extend(record(0x48, b""));
+        result.extend(record(0x31, &offset.to_le_bytes()));
+        result.extend(record(0x1e, &0_u32.to_le_bytes()));
+        result.extend(record(0x2c, &0xffff_u16.to_le_bytes()));
+        result.extend(record(0x21, b""));
+        result.extend(record(0x2b, b""));
+    }
+    result.extend(record(0x10, b""));
+    result
+}
+
+// -------------------------------------------------------------------------
+// -------------------------------------------------------------------------
+
+#[test]
+fn copy_token_decoder() {
+    // CopyTokens store offset and length information in a single 16-bit value. The bit
+    // range used for either one changes with the current position in the output stream.
+    //
+    // This test verifies the implementation by running the decompressor against input
+    // where the first CopyToken is encountered at positions 31, 32, and 33 in the
+    // output stream.
+    //
+    // The input was generated using Excel 2013, by adding non-repeating character
+    // sequences to a module, until the full size reached the desired length.
+    // The prefix `Attribute VB_Name = "a"\r\n` gets added by Excel for every code
+    // module, where `"a"` is the respective module name.
+    // The resulting *vbaProject.bin* files were then extracted from the Excel documents,
+    // opened in a hex editor, and the byte sequences corresponding to the respective
+    // compressed containers copied here.
+
+    // CompressedContainer with first CopyToken at position 31:
+    // 01 27 B0 00 41 74 74 72 69 62 75 74 00 65 20 56 42 5F 4E 61 6D 00 65 20 3D 20 22 61 22 0D 80 0A 61 62 63 64 65 66 06 F0 00 0D 0A
+    const CONTAINER_1: &[u8] = b"\x01\x27\xB0\x00\x41\x74\x74\x72\x69\x62\x75\x74\x00\x65\x20\x56\x42\x5F\x4E\x61\x6D\x00\x65\x20\x3D\x20\x22\x61\x22\x0D\x80\x0A\x61\x62\x63\x64\x65\x66\x06\xF0\x00\x0D\x0A";
+    const CONTENTS_1: &[u8] = b"Attribute VB_Name = \"a\"\x0D\x0AabcdefAttribute\x0D\x0A";
+    let contents = decompress(CONTAINER_1).unwrap().1;
+    assert_eq!(contents, CONTENTS_1);
+
+    // CompressedContainer with first CopyToken at position 32:
+    // 01 28 B0 00 41 74 74 72 69 62 75 74 00 65 20 56 42 5F 4E 61 6D 00 65 20 3D 20 22 61 22 0D 00 0A 61 62 63 64 65 66 67 01 06 F8 0D 0A
+    const CONTAINER_2: &[u8] = b"\x01\x28\xB0\x00\x41\x74\x74\x72\x69\x62\x75\x74\x00\x65\x20\x56\x42\x5F\x4E\x61\x6D\x00\x65\x20\x3D\x20\x22\x61\x22\x0D\x00\x0A\x61\x62\x63\x64\x65\x66\x67\x01\x06\xF8\x0D\x0A";
+    const CONTENTS_2: &[u8] = b"Attribute VB_Name = \"a\"\x0D\x0AabcdefgAttribute\x0D\x0A";
+    let contents = decompress(CONTAINER_2).unwrap().1;
+    assert_eq!(contents, CONTENTS_2);
+
+    // CompressedContainer with first CopyToken at position 33:
+    // 01 29 B0 00 41 74 74 72 69 62 75 74 00 65 20 56 42 5F 4E 61 6D 00 65 20 3D 20 22 61 22 0D 00 0A 61 62 63 64 65 66 67 02 68 06 80 0D 0A
+    const CONTAINER_3: &[u8] = b"\x01\x29\xB0\x00\x41\x74\x74\x72\x69\x62\x75\x74\x00\x65\x20\x56\x42\x5F\x4E\x61\x6D\x00\x65\x20\x3D\x20\x22\x61\x22\x0D\x00\x0A\x61\x62\x63\x64\x65\x66\x67\x02\x68\x06\x80\x0D\x0A";
+    const CONTENTS_3: &[u8] = b"Attribute VB_Name = \"a\"\x0D\x0AabcdefghAttribute\x0D\x0A";
+    let contents = decompress(CONTAINER_3).unwrap().1;
+    assert_eq!(contents, CONTENTS_3);
+}
+
+#[test]
+fn proj_info_opt_records() {
+    // Version 11 of the `[MS-OVBA]` specification introduced an optional
+    // `PROJECTCOMPATVERSION` record following the `PROJECTSYSKIND` record. This test
+    // verifies that this addition is properly handled by the parser.
+    //
+    // In addition, this test verifies that the final `PROJECTCONSTANTS` is treated as
+    // optional (which it should have been all along).
+    //
+    // The four test inputs represent the `2x2` matrix of combinations of optional
+    // records. Absent records are omitted entirely (as opposed to being present with
+    // empty data).
+
+    const INPUT_NONE_NONE: &[u8] = b"\x01\x00\x04\x00\x00\x00\x02\x00\x00\x00\
+        \x02\x00\x04\x00\x00\x00\x09\x04\x00\x00\
+        \x14\x00\x04\x00\x00\x00\x09\x04\x00\x00\
+        \x03\x00\x02\x00\x00\x00\xE4\x04\
+        \x04\x00\x01\x00\x00\x00\x41\
+        \x05\x00\x01\x00\x00\x00\x41\x40\x00\x02\x00\x00\x00\x41\x00\
+        \x06\x00\x00\x00\x00\x00\x3D\x00\x00\x00\x00\x00\
+        \x07\x00\x04\x00\x00\x00\x00\x00\x00\x00\
+        \x08\x00\x04\x00\x00\x00\x00\x00\x00\x00\
+        \x09\x00\x04\x00\x00\x00\x00\x00\x00\x00\x00\x00\
+        \x0F\x00\x02\x00\x00\x00\x00\x00\
+        \x13\x00\x02\x00\x00\x00\xFF\xFF\
+        \x10\x00\
+        \x00\x00\x00\x00";
+    let res = parse_project_information(INPUT_NONE_NONE);
+    assert!(res.is_ok());
+    let res = res.unwrap();
+    assert!(res.1.information.compat.is_none());
+    assert!(res.1.information.constants.is_none());
+    assert_eq!(res.1.information.constants(), "");
+
+    const INPUT_NONE_SOME: &[u8] = b"\x01\x00\x04\x00\x00\x00\x02\x00\x00\x00\
+        \x02\x00\x04\x00\x00\x00\x09\x04\x00\x00\
+        \x14\x00\x04\x00\x00\x00\x09\x04\x00\x00\
+        \x03\x00\x02\x00\x00\x00\xE4\x04\
+        \x04\x00\x01\x00\x00\x00\x41\
+        \x05\x00\x01\x00\x00\x00\x41\x40\x00\x02\x00\x00\x00\x41\x00\
+        \x06\x00\x00\x00\x00\x00\x3D\x00\x00\x00\x00\x00\
+        \x07\x00\x04\x00\x00\x00\x00\x00\x00\x00\
+        \x08\x00\x04\x00\x00\x00\x00\x00\x00\x00\
+        \x09\x00\x04\x00\x00\x00\x00\x00\x00\x00\x00\x00\
+        \x0C\x00\x00\x00\x00\x00\x3C\x00\x00\x00\x00\x00\
+        \x0F\x00\x02\x00\x00\x00\x00\x00\
+        \x13\x00\x02\x00\x00\x00\xFF\xFF\
+        \x10\x00\
+        \x00\x00\x00\x00";
+    let res = parse_project_information(INPUT_NONE_SOME);
+    assert!(res.is_ok());
+    let res = res.unwrap();
+    assert!(res.1.information.compat.is_none());
+    assert_eq!(res.1.information.constants.as_deref(), Some(""));
+
+    const INPUT_SOME_NONE: &[u8] = b"\x01\x00\x04\x00\x00\x00\x02\x00\x00\x00\
+        \x4A\x00\x04\x00\x00\x00\x01\x02\x03\x04\
+        \x02\x00\x04\x00\x00\x00\x09\x04\x00\x00\
+        \x14\x00\x04\x00\x00\x00\x09\x04\x00\x00\
+        \x03\x00\x02\x00\x00\x00\xE4\x04\
+        \x04\x00\x01\x00\x00\x00\x41\
+        \x05\x00\x01\x00\x00\x00\x41\x40\x00\x02\x00\x00\x00\x41\x00\
+        \x06\x00\x00\x00\x00\x00\x3D\x00\x00\x00\x00\x00\
+        \x07\x00\x04\x00\x00\x00\x00\x00\x00\x00\
+        \x08\x00\x04\x00\x00\x00\x00\x00\x00\x00\
+        \x09\x00\x04\x00\x00\x00\x00\x00\x00\x00\x00\x00\
+        \x0F\x00\x02\x00\x00\x00\x00\x00\
+        \x13\x00\x02\x00\x00\x00\xFF\xFF\
+        \x10\x00\
+        \x00\x00\x00\x00";
+    let res = parse_project_information(INPUT_SOME_NONE);
+    assert!(res.is_ok());
+    let res = res.unwrap();
+    assert_eq!(res.1.information.compat, Some(0x0403_0201));
+    assert!(res.1.information.constants.is_none());
+
+    const INPUT_SOME_SOME: &[u8] = b"\x01\x00\x04\x00\x00\x00\x02\x00\x00\x00\
+        \x4A\x00\x04\x00\x00\x00\x01\x02\x03\x04\
+        \x02\x00\x04\x00\x00\x00\x09\x04\x00\x00\
+        \x14\x00\x04\x00\x00\x00\x09\x04\x00\x00\
+        \x03\x00\x02\x00\x00\x00\xE4\x04\
+        \x04\x00\x01\x00\x00\x00\x41\
+        \x05\x00\x01\x00\x00\x00\x41\x40\x00\x02\x00\x00\x00\x41\x00\
+        \x06\x00\x00\x00\x00\x00\x3D\x00\x00\x00\x00\x00\
+        \x07\x00\x04\x00\x00\x00\x00\x00\x00\x00\
+        \x08\x00\x04\x00\x00\x00\x00\x00\x00\x00\
+        \x09\x00\x04\x00\x00\x00\x00\x00\x00\x00\x00\x00\
+        \x0C\x00\x00\x00\x00\x00\x3C\x00\x00\x00\x00\x00\
+        \x0F\x00\x02\x00\x00\x00\x00\x00\
+        \x13\x00\x02\x00\x00\x00\xFF\xFF\
+        \x10\x00\
+        \x00\x00\x00\x00";
+    let res = parse_project_information(INPUT_SOME_SOME);
+    assert!(res.is_ok());
+    let res = res.unwrap();
+    assert_eq!(res.1.information.compat, Some(0x0403_0201));
+    assert_eq!(res.1.information.constants.as_deref(), Some(""));
+}
+
+#[test]
+fn forward_compat_unknown_records() {
+    // A `PROJECTINFORMATION` record with an unknown record (ID 0x0099, 2 bytes of
+    // payload) following the `PROJECTLIBFLAGS` record.
+    const INPUT: &[u8] = b"\x01\x00\x04\x00\x00\x00\x02\x00\x00\x00\
+        \x02\x00\x04\x00\x00\x00\x09\x04\x00\x00\
+        \x14\x00\x04\x00\x00\x00\x09\x04\x00\x00\
+        \x03\x00\x02\x00\x00\x00\xE4\x04\
+        \x04\x00\x01\x00\x00\x00\x41\
+        \x05\x00\x01\x00\x00\x00\x41\x40\x00\x02\x00\x00\x00\x41\x00\
+        \x06\x00\x00\x00\x00\x00\x3D\x00\x00\x00\x00\x00\
+        \x07\x00\x04\x00\x00\x00\x00\x00\x00\x00\
+        \x08\x00\x04\x00\x00\x00\x00\x00\x00\x00\
+        \x99\x00\x02\x00\x00\x00\xAB\xCD\
+        \x09\x00\x04\x00\x00\x00\x00\x00\x00\x00\x00\x00\
+        \x0F\x00\x02\x00\x00\x00\x00\x00\
+        \x13\x00\x02\x00\x00\x00\xFF\xFF\
+        \x10\x00\
+        \x00\x00\x00\x00";
+
+    // Strict (default) mode rejects the unknown record.
+    assert!(parse_project_information(INPUT).is_err());
+
+    // Forward-compatibility mode skips it, and records a warning.
+    let mut state = ParseState::new(OpenOptions::new().forward_compat(true), INPUT);
+    let res = parse_project_information_with(INPUT, &mut state);
+    assert!(res.is_ok());
+    assert_eq!(
+        state.warnings,
+        vec![Warning::UnknownRecord {
+            id: 0x0099,
+            offset: 0x5C,
+            size: 2
+        }]
+    );
+}
+
+#[test]
+fn error_offsets() {
+    // Invalid `SysKind` value (0x07).
+    const INPUT: &[u8] = b"\x01\x00\x04\x00\x00\x00\x07\x00\x00\x00";
+    let err = parse_project_information(INPUT).unwrap_err();
+    let err = dir_parser_error(INPUT, err);
+    assert!(matches!(
+        err,
+        Error::Parser {
+            offset: 0,
+            kind: ParseErrorKind::UnexpectedValue,
+            record: Some("PROJECTSYSKIND")
+        }
+    ));
+    assert_eq!(err.offset(), Some(0));
+    assert_eq!(
+        err.to_string(),
+        "Parse error in PROJECTSYSKIND at offset 0x0: unexpected value"
+    );
+
+    // Invalid `CompressedChunkHeader` signature in the second chunk.
+    const CONTAINER: &[u8] = b"\x01\x00\xB0\x41\x00\x00\x41";
+    let err = decompress(CONTAINER).unwrap_err();
+    let err = decompressor_error(CONTAINER, err);
+    assert_eq!(err.offset(), Some(4));
+}
+
+#[test]
+fn truncated_project_name() {
+    // `PROJECTNAME` record declaring 16 bytes of data, with only 1 byte remaining.
+    const INPUT: &[u8] = b"\x01\x00\x04\x00\x00\x00\x02\x00\x00\x00\
+        \x02\x00\x04\x00\x00\x00\x09\x04\x00\x00\
+        \x14\x00\x04\x00\x00\x00\x09\x04\x00\x00\
+        \x03\x00\x02\x00\x00\x00\xE4\x04\
+        \x04\x00\x10\x00\x00\x00\x41";
+    let err = parse_project_information(INPUT).unwrap_err();
+    let err = parser_error(INPUT, err);
+    assert!(matches!(
+        err,
+        Error::Truncated {
+            offset: 44,
+            needed: 16,
+            available: 1
+        }
+    ));
+}
+
+#[test]
+fn reference_name_without_unicode() {
+    // `PROJECTINFORMATION` record, followed by a `REFERENCEREGISTERED` record, whose
+    // `REFERENCENAME` record lacks the `NameUnicode` portion.
+    const INFORMATION: &[u8] = b"\x01\x00\x04\x00\x00\x00\x02\x00\x00\x00\
+        \x02\x00\x04\x00\x00\x00\x09\x04\x00\x00\
+        \x14\x00\x04\x00\x00\x00\x09\x04\x00\x00\
+        \x03\x00\x02\x00\x00\x00\xE4\x04\
+        \x04\x00\x01\x00\x00\x00\x41\
+        \x05\x00\x01\x00\x00\x00\x41\x40\x00\x02\x00\x00\x00\x41\x00\
+        \x06\x00\x00\x00\x00\x00\x3D\x00\x00\x00\x00\x00\
+        \x07\x00\x04\x00\x00\x00\x00\x00\x00\x00\
+        \x08\x00\x04\x00\x00\x00\x00\x00\x00\x00\
+        \x09\x00\x04\x00\x00\x00\x00\x00\x00\x00\x00\x00";
+    const REFERENCE: &[u8] = b"\x16\x00\x06\x00\x00\x00stdole\
+        \x0D\x00\x19\x00\x00\x00\x0F\x00\x00\x00*\\G{00020430}#2\
+        \x00\x00\x00\x00\x00\x00";
+    const MODULES: &[u8] = b"\x0F\x00\x02\x00\x00\x00\x00\x00\
+        \x13\x00\x02\x00\x00\x00\xFF\xFF\
+        \x10\x00\
+        \x00\x00\x00\x00";
+    let input = [INFORMATION, REFERENCE, MODULES].concat();
+
+    let (_, res) = parse_project_information(&input).unwrap();
+    assert_eq!(res.references.len(), 1);
+    assert!(matches!(
+        &res.references[0],
+        Reference::Registered(reference) if reference.name.as_deref() == Some("stdole")
+    ));
+}
+
+#[test]
+fn lenient_terminator() {
+    const INPUT: &[u8] = b"\x01\x00\x04\x00\x00\x00\x02\x00\x00\x00\
+        \x02\x00\x04\x00\x00\x00\x09\x04\x00\x00\
+        \x14\x00\x04\x00\x00\x00\x09\x04\x00\x00\
+        \x03\x00\x02\x00\x00\x00\xE4\x04\
+        \x04\x00\x01\x00\x00\x00\x41\
+        \x05\x00\x01\x00\x00\x00\x41\x40\x00\x02\x00\x00\x00\x41\x00\
+        \x06\x00\x00\x00\x00\x00\x3D\x00\x00\x00\x00\x00\
+        \x07\x00\x04\x00\x00\x00\x00\x00\x00\x00\
+        \x08\x00\x04\x00\x00\x00\x00\x00\x00\x00\
+        \x09\x00\x04\x00\x00\x00\x00\x00\x00\x00\x00\x00\
+        \x0F\x00\x02\x00\x00\x00\x00\x00\
+        \x13\x00\x02\x00\x00\x00\xFF\xFF";
+    const TERMINATOR: &[u8] = b"\x10\x00\x00\x00\x00\x00";
+    let lenient = OpenOptions::new().lenient(true).clone();
+
+    // Missing terminator
+    assert!(parse_project_information(INPUT).is_err());
+    let mut state = ParseState::new(&lenient, INPUT);
+    assert!(parse_project_information_with(INPUT, &mut state).is_ok());
+    assert_eq!(
+        state.warnings,
+        vec![Warning::MissingTerminator { offset: 0x78 }]
+    );
+
+    // Trailing data
+    let input = [INPUT, TERMINATOR, b"\x00\x00"].concat();
+    assert!(matches!(
+        ensure_consumed(&input, &input[0x7E..]),
+        Err(Error::Parser {
+            offset: 0x7E,
+            kind: ParseErrorKind::TrailingData,
+            record: None
+        })
+    ));
+    let err = parse_project_information(&input).unwrap_err();
+    assert!(matches!(
+        parser_error(&input, err),
+        Error::Parser {
+            offset: 0x7E,
+            kind: ParseErrorKind::TrailingData,
+            record: None
+        }
+    ));
+    let mut state = ParseState::new(&lenient, &input);
+    let (remainder, _) = parse_project_information_with(&input, &mut state).unwrap();
+    assert!(ensure_consumed(&input, remainder).is_ok());
+    assert_eq!(
+        state.warnings,
+        vec![Warning::TrailingData {
+            offset: 0x7E,
+            size: 2
+        }]
+    );
+}
+
+#[test]
+fn code_page_before_strings() {
+    const PREFIX: &[u8] = b"\x01\x00\x04\x00\x00\x00\x02\x00\x00\x00\
+        \x02\x00\x04\x00\x00\x00\x09\x04\x00\x00\
+        \x14\x00\x04\x00\x00\x00\x09\x04\x00\x00";
+
+    // `PROJECTNAME` record preceding the `PROJECTCODEPAGE` record.
+    let input = [
+        PREFIX,
+        b"\x04\x00\x01\x00\x00\x00\x41\x03\x00\x02\x00\x00\x00\xE4\x04",
+    ]
+    .concat();
+    let err = parse_project_information(&input).unwrap_err();
+    assert!(matches!(
+        parser_error(&input, err),
+        Error::Parser {
+            offset: 30,
+            kind: ParseErrorKind::UnexpectedTag,
+            record: None
+        }
+    ));
+
+    // Code page that doesn't map to an encoding.
+    let input = [
+        PREFIX,
+        b"\x03\x00\x02\x00\x00\x00\x00\x00\x04\x00\x01\x00\x00\x00\x41",
+    ]
+    .concat();
+    let err = parse_project_information(&input).unwrap_err();
+    assert!(matches!(
+        parser_error(&input, err),
+        Error::Encoding { code_page: 0 }
+    ));
+}
+
+#[test]
+fn predicted_decompressed_size_from_headers() {
+    // Compressed chunks are accounted for with 4096 bytes each.
+    let data = compress_literals(&[b'A'; 5000]);
+    assert_eq!(predicted_decompressed_size(&data).unwrap(), 2 * 4096);
+
+    // Uncompressed chunks are accounted for with 4096 bytes, or the remaining input size
+    // for a final chunk.
+    let mut data = vec![0x01, 0xff, 0x3f];
+    data.extend_from_slice(&[b'A'; 4096]);
+    data.extend_from_slice(&[0x09, 0x30]);
+    data.extend_from_slice(&[b'B'; 10]);
+    assert_eq!(predicted_decompressed_size(&data).unwrap(), 4106);
+    assert_eq!(decompress(&data).unwrap().1.len(), 4106);
+
+    // Header validation matches the decompressor.
+    let err = predicted_decompressed_size(&[0x01, 0x09, 0xb0, b'B']).unwrap_err();
+    assert!(matches!(err, Error::Decompressor { offset: 3 }));
+    let err = predicted_decompressed_size(&[0x01, 0x00, 0x00]).unwrap_err();
+    assert_eq!(err.offset(), Some(1));
+}
+
+#[test]
+fn module_doc_string_unicode() {
+    // A module description that cannot be represented in code page 1252, with an empty
+    // MBCS string.
+    let description: Vec<u8> = "Описание"
+        .encode_utf16()
+        .flat_map(|c| c.to_le_bytes())
+        .collect();
+    let empty_doc_string = b"\x1c\x00\x00\x00\x00\x00\x48\x00\x00\x00\x00\x00";
+    let mut doc_string = b"\x1c\x00\x00\x00\x00\x00\x48\x00".to_vec();
+    doc_string.extend_from_slice(&(description.len() as u32).to_le_bytes());
+    doc_string.extend_from_slice(&description);
+
+    let dir = dir_stream(&[("a", "a", 0)]);
+    let pos = dir
+        .windows(empty_doc_string.len())
+        .position(|w| w == empty_doc_string)
+        .unwrap();
+    let dir = [
+        &dir[..pos],
+        &doc_string,
+        &dir[pos + empty_doc_string.len()..],
+    ]
+    .concat();
+    let (_, information) = parse_project_information(&dir).unwrap();
+
+    assert_eq!(information.modules[0].doc_string, "Описание");
+}
+
+#[test]
+fn dir_records() {
+    let dir = dir_stream(&[("a", "a", 0)]);
+    let records = iter_dir_records(&dir)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let ids = records.iter().map(|record| record.id).collect::<Vec<_>>();
+    assert_eq!(
+        ids,
+        [
+            0x01, 0x02, 0x14, 0x03, 0x04, 0x05, 0x40, 0x06, 0x3d, 0x07, 0x08, 0x09, 0x0f, 0x13,
+            0x19, 0x47, 0x1a, 0x32, 0x1c, 0x48, 0x31, 0x1e, 0x2c, 0x21, 0x2b, 0x10
+        ]
+    );
+    // `PROJECTVERSION` carries 6 bytes of data
+    assert_eq!(records[11].payload, b"\x01\x00\x00\x00\x00\x00");
+    assert_eq!(records[14].payload, b"a");
+
+    // Errors are reported once, with offsets relative to the start of the input.
+    let input = [&dir[..10], b"\x04\x00\x05\x00\x00\x00AB"].concat();
+    let mut records = iter_dir_records(&input);
+    assert!(records.next().unwrap().is_ok());
+    let err = records.next().unwrap().unwrap_err();
+    assert!(matches!(
+        err,
+        Error::Truncated {
+            offset: 16,
+            needed: 5,
+            available: 2,
+        }
+    ));
+    assert!(records.next().is_none());
+}
+
+/// Builds a REFERENCE consisting of a REFERENCENAME, REFERENCEORIGINAL, and
+/// REFERENCECONTROL record.
+pub(super) fn control_reference(name: &str, guid: &[u8; 16]) -> Vec<u8> {
+    fn sized(data: &[u8]) -> Vec<u8> {
+        [&(data.len() as u32).to_le_bytes(), data].concat()
+    }
+    const LIBID_ORIGINAL: &[u8] =
+        b"*\\G{00020430-0000-0000-C000-000000000046}#2.0#0#stdole2.tlb#OLE Automation";
+    const LIBID_TWIDDLED: &[u8] = b"*\\G{00000000-0000-0000-0000-000000000000}#0.0#0##";
+    const LIBID_EXTENDED: &[u8] =
+        b"*\\G{00000000-0000-0000-0000-000000000001}#2.0#0#ext.exd#Extended";
+
+    let twiddled = [sized(LIBID_TWIDDLED), vec![0; 6]].concat();
+    let extended = [
+        sized(LIBID_EXTENDED),
+        vec![0; 6],
+        guid.to_vec(),
+        7_u32.to_le_bytes().to_vec(),
+    ]
+    .concat();
+    [
+        b"\x16\x00".to_vec(),
+        sized(name.as_bytes()),
+        b"\x3e\x00\x00\x00\x00\x00".to_vec(),
+        b"\x33\x00".to_vec(),
+        sized(LIBID_ORIGINAL),
+        b"\x2f\x00".to_vec(),
+        sized(&twiddled),
+        b"\x16\x00".to_vec(),
+        sized(b"ext"),
+        b"\x30\x00".to_vec(),
+        sized(&extended),
+    ]
+    .concat()
+}
+
+#[test]
+fn code_page_replacement() {
+    // Code page 874 (Thai) leaves several byte positions undefined, e.g. 0xDB-0xDE and
+    // 0xFC-0xFF. Most of the 0x80-0x9F range decodes to C1 control characters.
+    let data = b"\x80\x81\x9f\xa1\xdb\xde\xfc\xff";
+    assert_eq!(
+        cp_to_string(data, 874).unwrap(),
+        "€\u{81}\u{9F}ก\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}"
+    );
+
+    // Truncated MBCS sequence in code page 932 (Shift JIS)
+    assert_eq!(cp_to_string(b"A\x82", 932).unwrap(), "A\u{FFFD}");
+}
+
+#[test]
+fn reference_control_without_extended() {
+    // A `REFERENCECONTROL` record lacking the extended type library, followed by a named
+    // `REFERENCEREGISTERED` record.
+    const REFERENCES: &[u8] = b"\x2F\x00\x19\x00\x00\x00\x0F\x00\x00\x00*\\G{00000000}#0\
+        \x00\x00\x00\x00\x00\x00\
+        \x16\x00\x06\x00\x00\x00stdole\x3E\x00\x00\x00\x00\x00\
+        \x0D\x00\x19\x00\x00\x00\x0F\x00\x00\x00*\\G{00020430}#2\
+        \x00\x00\x00\x00\x00\x00";
+    let dir = dir_stream_with_references(REFERENCES, &[]);
+    let (_, res) = parse_project_information(&dir).unwrap();
+
+    assert_eq!(res.references.len(), 2);
+    let control = match &res.references[0] {
+        Reference::Control(control) => control,
+        _ => panic!("expected a control reference"),
+    };
+    assert_eq!(control.libid_twiddled(), "*\\G{00000000}#0");
+    assert_eq!(control.name_extended(), None);
+    assert_eq!(control.libid_extended(), "");
+    assert_eq!(control.guid(), &[0; 16]);
+    assert_eq!(res.references[1].name(), Some("stdole"));
+}
+
+#[test]
+fn copy_token_out_of_bounds() {
+    // CopyToken at the start of a chunk, referring to data preceding the chunk
+    const CONTAINER_1: &[u8] = b"\x01\x02\xB0\x01\x00\x00";
+    let err = decompress(CONTAINER_1).unwrap_err();
+    assert!(matches!(
+        decompressor_error(CONTAINER_1, err),
+        Error::Decompressor { .. }
+    ));
+
+    // CopyToken with an offset exceeding the output produced so far
+    const CONTAINER_2: &[u8] = b"\x01\x04\xB0\x02\x41\x00\x10";
+    let err = decompress(CONTAINER_2).unwrap_err();
+    assert!(matches!(
+        decompressor_error(CONTAINER_2, err),
+        Error::Decompressor { .. }
+    ));
+
+    // CopyTokens growing the output past the maximum chunk size of 4096 bytes
+    let mut container = vec![0x01, 0x00, 0x00, 0x00, 0x41];
+    for _ in 0..48 {
+        container.extend_from_slice(
+            b"\xFF\xFF\x0F\xFF\x0F\xFF\x0F\xFF\x0F\xFF\x0F\xFF\x0F\xFF\x0F\xFF\x0F",
+        );
+    }
+    let header = 0xb000_u16 | (container.len() - 4) as u16;
+    container[1..3].copy_from_slice(&header.to_le_bytes());
+    assert!(decompress(&container).is_err());
+}
+
+#[test]
+fn compress_roundtrip() {
+    // Pseudo-random, mostly incompressible input
+    let mut state = 0x1234_5678_u32;
+    let noise = (0..10_000)
+        .map(|_| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (state >> 16) as u8
+        })
+        .collect::<Vec<_>>();
+    let source = b"Sub Main()\r\n    MsgBox \"Hello, world!\"\r\nEnd Sub\r\n".repeat(300);
+
+    for data in [
+        &b""[..],
+        b"A",
+        b"AAAAAAAAAAAAAAAAAAAAAAAA",
+        &source,
+        &source[..4096],
+        &noise,
+        &noise[..3700],
+    ] {
+        let container = compress(data);
+        assert_eq!(decompress(&container).unwrap().1, data);
+    }
+
+    // Repetitive input shrinks.
+    assert!(compress(&source).len() < source.len() / 10);
+
+    // The public counterpart reports errors with offsets into the container.
+    let mut container = compress(&source);
+    assert_eq!(super::decompress(&container).unwrap(), source);
+    container.push(0x00);
+    let offset = container.len() - 1;
+    assert!(matches!(
+        super::decompress(&container),
+        Err(Error::Decompressor { offset: o }) if o == offset
+    ));
+}
+
+#[test]
+fn parse_dir_without_container() {
+    let dir = dir_stream(&[("a", "a", 0x10)]);
+    let metadata = parse_dir(&dir).unwrap();
+    assert_eq!(metadata.information.name, "VBAProject");
+    assert_eq!(metadata.modules.len(), 1);
+    assert_eq!(metadata.modules[0].text_offset, 0x10);
+
+    // Trailing data is rejected, unless parsing leniently.
+    let input = [&dir[..], b"\x00\x00"].concat();
+    assert!(matches!(
+        parse_dir(&input),
+        Err(Error::Parser {
+            kind: ParseErrorKind::TrailingData,
+            ..
+        })
+    ));
+    let (metadata, warnings) = OpenOptions::new().lenient(true).parse_dir(&input).unwrap();
+    assert_eq!(metadata.modules.len(), 1);
+    assert!(matches!(warnings[..], [Warning::TrailingData { .. }]));
+}
+
+#[test]
+fn module_flag_record_order() {
+    const READ_ONLY: &[u8] = b"\x25\x00\x00\x00\x00\x00";
+    const PRIVATE: &[u8] = b"\x28\x00\x00\x00\x00\x00";
+    // Inserts `records` between the MODULETYPE record and the module's terminator.
+    let with_records = |records: &[&[u8]]| {
+        let dir = dir_stream(&[("a", "a", 0)]);
+        let position = dir
+            .windows(2)
+            .rposition(|window| window == b"\x2b\x00")
+            .unwrap();
+        [&dir[..position], &records.concat(), &dir[position..]].concat()
+    };
+
+    let (_, in_order) = parse_project_information(&with_records(&[READ_ONLY, PRIVATE])).unwrap();
+    let (_, swapped) = parse_project_information(&with_records(&[PRIVATE, READ_ONLY])).unwrap();
+    assert!(in_order.modules[0].read_only && in_order.modules[0].private);
+    assert_eq!(in_order.modules, swapped.modules);
+
+    let (_, private) = parse_project_information(&with_records(&[PRIVATE])).unwrap();
+    assert!(!private.modules[0].read_only && private.modules[0].private);
+
+    assert!(parse_project_information(&with_records(&[PRIVATE, PRIVATE])).is_err());
+}
+
+#[test]
+fn dir_builder_roundtrip() {
+    fn sized(data: &[u8]) -> Vec<u8> {
+        [&(data.len() as u32).to_le_bytes(), data].concat()
+    }
+    // REFERENCEREGISTERED and REFERENCEPROJECT, the former preceded by a REFERENCENAME
+    let registered = [&sized(b"*\\G{00020430}#2")[..], b"\x00\x00\x00\x00\x00\x00"].concat();
+    let project = [
+        &sized(b"*\\CC:\\Other.xlsm")[..],
+        &sized(b"*\\COther.xlsm"),
+        b"\x01\x00\x00\x00\x02\x00",
+    ]
+    .concat();
+    let references = [
+        &b"\x16\x00"[..],
+        &sized(b"stdole"),
+        b"\x3e\x00",
+        &sized(b"s\x00t\x00d\x00o\x00l\x00e\x00"),
+        b"\x0d\x00",
+        &sized(&registered),
+        b"\x0e\x00",
+        &sized(&project),
+    ]
+    .concat();
+    let dir = dir_stream_with_references(&references, &[("a", "a", 0), ("b", "c", 0x20)]);
+
+    let metadata = parse_dir(&dir).unwrap();
+    assert_eq!(metadata.references.len(), 2);
+    assert_eq!(DirBuilder::from(metadata.clone()).build().unwrap(), dir);
+
+    let mut builder = DirBuilder::new(metadata.information.clone());
+    builder.module(metadata.modules[1].clone());
+    let rebuilt = parse_dir(&builder.build().unwrap()).unwrap();
+    assert_eq!(rebuilt.information, metadata.information);
+    assert!(rebuilt.references.is_empty());
+    assert_eq!(rebuilt.modules, metadata.modules[1..]);
+
+    // Unicode duplicates that don't match their MBCS counterparts aren't reproduced, but
+    // the rebuilt stream parses to the same data.
+    let control = control_reference(
+        "MSForms",
+        b"\xE1\x2E\x45\x0D\x8F\xE0\x1A\x10\x85\x2E\x02\x60\x8C\x4D\x0B\xB4",
+    );
+    let dir = dir_stream_with_references(&control, &[("a", "a", 0)]);
+    let metadata = parse_dir(&dir).unwrap();
+    let rebuilt = DirBuilder::from(metadata.clone()).build().unwrap();
+    assert_ne!(rebuilt, dir);
+    let rebuilt = parse_dir(&rebuilt).unwrap();
+    assert_eq!(rebuilt.references, metadata.references);
+    assert_eq!(rebuilt.modules, metadata.modules);
+}
+
+#[test]
+fn verify_duplicates() {
+    let mut options = OpenOptions::new();
+    options.verify_duplicates(true);
+
+    let dir = dir_stream(&[("a", "a", 0)]);
+    let (_, warnings) = options.parse_dir(&dir).unwrap();
+    assert!(warnings.is_empty());
+
+    // Replace the MODULENAMEUNICODE record's data.
+    let mut tampered = dir.clone();
+    let position = tampered
+        .windows(8)
+        .position(|window| window == b"\x47\x00\x02\x00\x00\x00a\x00")
+        .unwrap();
+    tampered[position + 6] = b'b';
+    let (metadata, warnings) = options.parse_dir(&tampered).unwrap();
+    assert_eq!(metadata.modules[0].name, "a");
+    assert_eq!(
+        warnings,
+        [Warning::MismatchedDuplicate {
+            id: 0x47,
+            offset: position,
+        }]
+    );
+    // Mismatches are ignored by default.
+    assert!(OpenOptions::new()
+        .parse_dir(&tampered)
+        .unwrap()
+        .1
+        .is_empty());
+
+    // The test reference's REFERENCENAMEUNICODE record is empty.
+    let control = control_reference("MSForms", &[0; 16]);
+    let dir = dir_stream_with_references(&control, &[]);
+    let (_, warnings) = options.parse_dir(&dir).unwrap();
+    assert!(matches!(
+        warnings[..],
+        [Warning::MismatchedDuplicate { id: 0x3e, .. }]
+    ));
+}
@@ -0,0 +1,234 @@
+#![forbid(unsafe_code)]
+
+use crate::ModuleAttributes;
+use std::borrow::Cow;
+
+/// Splits module source code into the leading `Attribute` statements and the remainder.
+///
+/// Office VBA stores a module's attributes as a contiguous block of `Attribute` lines at
+/// the start of the source code. Source code authored in VB6 can additionally start
+/// with a file header (a `VERSION` line, `Object` lines, and a `BEGIN`/`END` block)
+/// ahead of the `Attribute` lines. That header is skipped.
+///
+/// Keywords are matched case-insensitively, and `Attribute` statements can span multiple
+/// lines using the ` _` line continuation sequence. Quoted values are unquoted.
+///
+/// Returns the attributes as `(name, value)` pairs, along with the source code following
+/// the attribute block.
+pub(crate) fn split_attributes(source: &str) -> (Vec<(String, String)>, &str) {
+    let mut lines = Lines { source, offset: 0 };
+
+    // VB6 file header
+    if matches!(lines.peek(), Some(line) if starts_with_keyword(line, "VERSION")) {
+        lines.next();
+        while let Some(line) = lines.peek() {
+            if starts_with_keyword(line, "Object") {
+                lines.next();
+            } else if starts_with_keyword(line, "Begin") {
+                skip_block(&mut lines);
+            } else {
+                break;
+            }
+        }
+    }
+
+    let mut attributes = Vec::new();
+    while let Some(line) = lines.peek() {
+        if !starts_with_keyword(line, "Attribute") {
+            break;
+        }
+        lines.next();
+        let mut statement = line.to_owned();
+        while let Some(continued) = strip_continuation(&statement) {
+            statement = continued.to_owned();
+            match lines.next() {
+                Some(line) => statement.push_str(line.trim_start()),
+                None => break,
+            }
+        }
+        if let Some(attribute) = parse_attribute(&statement) {
+            attributes.push(attribute);
+        }
+    }
+
+    (attributes, &source[lines.offset..])
+}
+
+/// Replaces the value of the `VB_Name` attribute in `source` with `name`.
+///
+/// Only the attribute block recognized by [`split_attributes`] is searched, and an
+/// attribute spanning multiple lines isn't matched. Returns `None` if no `VB_Name`
+/// attribute is found.
+pub(crate) fn replace_vb_name(source: &str, name: &str) -> Option<String> {
+    let (_, rest) = split_attributes(source);
+    let mut lines = Lines {
+        source: &source[..source.len() - rest.len()],
+        offset: 0,
+    };
+    while let Some((line, len)) = lines.line() {
+        let is_vb_name = starts_with_keyword(line, "Attribute")
+            && strip_continuation(line).is_none()
+            && matches!(parse_attribute(line), Some((key, _)) if key.eq_ignore_ascii_case("VB_Name"));
+        if is_vb_name {
+            let (head, tail) = (
+                &source[..lines.offset],
+                &source[lines.offset + line.len()..],
+            );
+            return Some(format!(r#"{}Attribute VB_Name = "{}"{}"#, head, name, tail));
+        }
+        lines.offset += len;
+    }
+    None
+}
+
+/// Iterates over the logical lines of `source`.
+///
+/// Physical lines are terminated by `"\r\n"`, and a terminator at the end of `source`
+/// doesn't start another line. A line ending in the ` _` line continuation sequence is
+/// joined with the following line, dropping the underscore, the line terminator, and the
+/// following line's leading whitespace. Only joined lines are allocated.
+pub(crate) fn logical_lines(source: &str) -> impl Iterator<Item = Cow<'_, str>> {
+    let source = source.strip_suffix("\r\n").unwrap_or(source);
+    let mut lines = Some(source)
+        .filter(|source| !source.is_empty())
+        .into_iter()
+        .flat_map(|source| source.split("\r\n"));
+    std::iter::from_fn(move || {
+        let mut line = Cow::Borrowed(lines.next()?);
+        while let Some(continued) = strip_continuation(&line) {
+            let next = match lines.next() {
+                Some(next) => next,
+                None => break,
+            };
+            let joined = [continued, next.trim_start()].concat();
+            line = Cow::Owned(joined);
+        }
+        Some(line)
+    })
+}
+
+/// Iterates over the lines of `source`, excluding line terminators, while keeping track
+/// of the offset of the next line.
+struct Lines<'a> {
+    source: &'a str,
+    offset: usize,
+}
+
+impl<'a> Lines<'a> {
+    fn peek(&self) -> Option<&'a str> {
+        self.line().map(|(line, _)| line)
+    }
+
+    fn line(&self) -> Option<(&'a str, usize)> {
+        let rest = &self.source[self.offset..];
+        if rest.is_empty() {
+            return None;
+        }
+        let len = rest.find('\n').map_or(rest.len(), |pos| pos + 1);
+        let line = rest[..len].trim_end_matches(['\r', '\n']);
+        Some((line, len))
+    }
+}
+
+impl<'a> Iterator for Lines<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (line, len) = self.line()?;
+        self.offset += len;
+        Some(line)
+    }
+}
+
+/// Skips a (possibly nested) `Begin`/`End` block of a VB6 file header.
+///
+/// VB6 form files additionally use `BeginProperty`/`EndProperty` blocks, which are
+/// matched the same way.
+fn skip_block(lines: &mut Lines<'_>) {
+    let mut depth = 0_usize;
+    for line in lines {
+        if starts_with_keyword(line, "Begin") || starts_with_keyword(line, "BeginProperty") {
+            depth += 1;
+        } else if starts_with_keyword(line, "End") || starts_with_keyword(line, "EndProperty") {
+            depth -= 1;
+            if depth == 0 {
+                return;
+            }
+        }
+    }
+}
+
+/// Returns whether the first word of `line` is `keyword`, compared case-insensitively.
+fn starts_with_keyword(line: &str, keyword: &str) -> bool {
+    let word = line
+        .trim_start()
+        .split(|c: char| c.is_whitespace())
+        .next()
+        .unwrap_or_default();
+    word.eq_ignore_ascii_case(keyword)
+}
+
+/// Returns `statement` without its trailing line continuation sequence, if present.
+fn strip_continuation(statement: &str) -> Option<&str> {
+    let statement = statement.trim_end();
+    let stripped = statement.strip_suffix('_')?;
+    if stripped.ends_with(char::is_whitespace) {
+        Some(stripped)
+    } else {
+        None
+    }
+}
+
+/// Parses an `Attribute <name> = <value>` statement.
+fn parse_attribute(statement: &str) -> Option<(String, String)> {
+    let statement = statement.trim_start();
+    let rest = statement["Attribute".len()..].trim_start();
+    let (name, value) = rest.split_once('=')?;
+    let name = name.trim();
+    let value = value.trim();
+    let value = match value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+    {
+        Some(value) => value.replace("\"\"", "\""),
+        None => value.to_owned(),
+    };
+    Some((name.to_owned(), value))
+}
+
+/// Interprets the `(name, value)` pairs returned by [`split_attributes`].
+///
+/// Names and boolean values are matched case-insensitively. If an attribute is declared
+/// more than once, the last declaration wins.
+pub(crate) fn module_attributes(attributes: Vec<(String, String)>) -> ModuleAttributes {
+    let mut result = ModuleAttributes::default();
+    for (name, value) in attributes {
+        let flag = match name.to_ascii_lowercase().as_str() {
+            "vb_name" => {
+                result.name = Some(value);
+                continue;
+            }
+            "vb_base" => {
+                result.base = Some(value);
+                continue;
+            }
+            "vb_globalnamespace" => &mut result.global_name_space,
+            "vb_creatable" => &mut result.creatable,
+            "vb_predeclaredid" => &mut result.predeclared_id,
+            "vb_exposed" => &mut result.exposed,
+            "vb_customizable" => &mut result.customizable,
+            _ => {
+                result.other.insert(name, value);
+                continue;
+            }
+        };
+        if value.eq_ignore_ascii_case("True") {
+            *flag = true;
+        } else if value.eq_ignore_ascii_case("False") {
+            *flag = false;
+        } else {
+            result.other.insert(name, value);
+        }
+    }
+    result
+}
@@ -1,29 +1,123 @@
 #![forbid(unsafe_code)]
 
-use std::fmt;
-use std::io;
-use std::{error, string};
+use alloc::{boxed::Box, string};
+use core::fmt;
+#[cfg(feature = "std")]
+use std::{error, io};
 
 /// A type alias for `Result<T, ovba::Error>`.
-pub type Result<T> = std::result::Result<T, Error>;
+pub type Result<T> = core::result::Result<T, Error>;
 
 /// Public error type.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
     /// I/O Error.
+    #[cfg(feature = "std")]
     Io(io::Error),
     /// Error originating from the cfb implementation.
+    #[cfg(feature = "std")]
     Cfb(io::Error),
-    // TODO: Add details to make the diagnostic more meaningful to clients.
     /// Error originating from the `CompressedContainer` decompressor.
-    Decompressor,
-    // TODO: Add details to make the diagnostic more meaningful to clients.
+    Decompressor {
+        /// Offset into the `CompressedContainer` where decompression failed.
+        offset: usize,
+    },
     /// Generic parsing error.
-    Parser,
+    Parser {
+        /// Offset into the decompressed input where parsing failed.
+        offset: usize,
+        /// Describes what went wrong.
+        kind: ParseErrorKind,
+        /// The name of the *dir* stream record that failed to parse, as used by the
+        /// specification (e.g. `"PROJECTMODULES"`), if known.
+        record: Option<&'static str>,
+    },
+    /// Length-prefixed data extends past the end of the input.
+    ///
+    /// This usually indicates a truncated file.
+    Truncated {
+        /// Offset into the decompressed input where the length-prefixed data starts.
+        offset: usize,
+        /// The declared length of the data.
+        needed: usize,
+        /// The number of bytes remaining in the input.
+        available: usize,
+    },
     /// Requested module cannot be found.
     ModuleNotFound(string::String),
+    /// A module's stream could not be read, or its source code could not be
+    /// decompressed.
+    ///
+    /// The underlying error is an [`Error::Cfb`] error if the module stream is missing or
+    /// cannot be read, and an [`Error::Decompressor`] error if decompression failed.
+    Module {
+        /// The module's name.
+        name: string::String,
+        /// The underlying error.
+        source: Box<Error>,
+    },
+    /// A module name is already in use, either by another module, or another entry of
+    /// the *VBA* storage.
+    DuplicateModule(string::String),
+    /// The requested modification isn't supported for this project. Holds the reason.
+    Unsupported(&'static str),
+    /// A module's stream name cannot be mapped to a CFB stream path.
+    ///
+    /// The stream name contains path separators, or is a relative path component (such
+    /// as `..`) on the target platform.
+    InvalidStreamName(string::String),
+    /// A string cannot be decoded, because its code page cannot be mapped to an
+    /// encoding.
+    Encoding {
+        /// The code page.
+        code_page: u16,
+    },
 }
 
+/// Specifies the category of an [`Error::Parser`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParseErrorKind {
+    /// A record signature or fixed-value field didn't match the expected value.
+    UnexpectedTag,
+    /// A field holds a value that isn't allowed by the specification.
+    UnexpectedValue,
+    /// The input ended before a record was complete.
+    UnexpectedEof,
+    /// Data remains after the final record.
+    TrailingData,
+    /// Any other parser error.
+    Other,
+}
+
+impl Error {
+    #[cfg(feature = "std")]
+    pub(crate) fn module(name: &str, source: Error) -> Self {
+        Error::Module {
+            name: name.to_owned(),
+            source: Box::new(source),
+        }
+    }
+
+    /// Returns the byte offset at which the error occurred, if known.
+    ///
+    /// The offset is reported for [`Error::Parser`], [`Error::Decompressor`], and
+    /// [`Error::Truncated`] errors. It is relative to the start of the respective
+    /// parser's input. For [`Error::Module`] errors, the offset of the
+    /// underlying error is reported.
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            Error::Decompressor { offset } => Some(*offset),
+            Error::Parser { offset, .. } => Some(*offset),
+            Error::Truncated { offset, .. } => Some(*offset),
+            Error::Module { source, .. } => source.offset(),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
 impl From<io::Error> for Error {
     // This provides automatic conversion from `io::Error` to `Error::Io`. The cfb crate doesn't provide a
     // custom error type and repurposes `io::Error` instead. Library code that handles cfb failures thus
@@ -33,14 +127,23 @@ impl From<io::Error> for Error {
     }
 }
 
+#[cfg(feature = "std")]
 impl error::Error for Error {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
         match self {
             Error::Io(e) => Some(e),
             Error::Cfb(e) => Some(e),
-            Error::Decompressor => None,
-            Error::Parser => None,
+            // The parser's internal error type isn't an `error::Error`. All relevant
+            // information is reported through the `Display` implementation instead.
+            Error::Decompressor { .. } => None,
+            Error::Parser { .. } => None,
+            Error::Truncated { .. } => None,
             Error::ModuleNotFound(_) => None,
+            Error::Module { source, .. } => Some(source.as_ref()),
+            Error::DuplicateModule(_) => None,
+            Error::Unsupported(_) => None,
+            Error::InvalidStreamName(_) => None,
+            Error::Encoding { .. } => None,
         }
     }
 }
@@ -48,11 +151,60 @@ impl error::Error for Error {
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            #[cfg(feature = "std")]
             Error::Io(e) => write!(f, "I/O error: {}", e),
+            #[cfg(feature = "std")]
             Error::Cfb(e) => write!(f, "CFB error: {}", e),
-            Error::Decompressor => write!(f, "Decompressor error"),
-            Error::Parser => write!(f, "Parse error"),
+            Error::Decompressor { offset } => {
+                write!(f, "Decompressor error at offset 0x{:X}", offset)
+            }
+            Error::Parser {
+                offset,
+                kind,
+                record: Some(record),
+            } => write!(
+                f,
+                "Parse error in {} at offset 0x{:X}: {}",
+                record, offset, kind
+            ),
+            Error::Parser {
+                offset,
+                kind,
+                record: None,
+            } => write!(f, "Parse error at offset 0x{:X}: {}", offset, kind),
+            Error::Truncated {
+                offset,
+                needed,
+                available,
+            } => write!(
+                f,
+                "Truncated input at offset 0x{:X}: {} bytes declared, {} bytes available",
+                offset, needed, available
+            ),
             Error::ModuleNotFound(name) => write!(f, r#"Module "{}" not found"#, name),
+            Error::Module { name, .. } => {
+                write!(f, r#"Failed to read source code of module "{}""#, name)
+            }
+            Error::DuplicateModule(name) => {
+                write!(f, r#"Module name "{}" is already in use"#, name)
+            }
+            Error::Unsupported(reason) => write!(f, "Unsupported modification: {}", reason),
+            Error::InvalidStreamName(name) => write!(f, r#"Invalid stream name "{}""#, name),
+            Error::Encoding { code_page } => {
+                write!(f, "Code page {} cannot be mapped to an encoding", code_page)
+            }
+        }
+    }
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseErrorKind::UnexpectedTag => write!(f, "unexpected signature or fixed value"),
+            ParseErrorKind::UnexpectedValue => write!(f, "unexpected value"),
+            ParseErrorKind::UnexpectedEof => write!(f, "unexpected end of input"),
+            ParseErrorKind::TrailingData => write!(f, "unexpected data after the final record"),
+            ParseErrorKind::Other => write!(f, "malformed input"),
         }
     }
 }
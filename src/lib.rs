@@ -4,24 +4,31 @@
 //! Structure][MS-OVBA] protocol (Revision 9.1, published 2020-02-19).
 //!
 //! The main entry point into the API is the [`Project`] type, returned by the
-//! [`open_project`] function.
+//! [`Project::open`] and [`open_project`] functions.
 //!
 //! # Usage
 //!
 //! Opening a project:
 //!
 //! ```rust,no_run
+//! # #[cfg(feature = "std")]
+//! # fn main() -> ovba::Result<()> {
 //! use std::fs::read;
 //! use ovba::open_project;
 //!
 //! let data = read("vbaProject.bin")?;
 //! let project = open_project(data)?;
-//! # Ok::<(), ovba::Error>(())
+//! # Ok(())
+//! # }
+//! # #[cfg(not(feature = "std"))]
+//! # fn main() {}
 //! ```
 //!
 //! A more complete example that dumps an entire VBA project's source code:
 //!
 //! ```rust,no_run
+//! # #[cfg(feature = "std")]
+//! # fn main() -> ovba::Result<()> {
 //! use std::fs::{read, write};
 //! use ovba::open_project;
 //!
@@ -32,13 +39,18 @@
 //!     let src_code = project.module_source_raw(&module.name)?;
 //!     write("./out/".to_string() + &module.name, src_code)?;
 //! }
-//! # Ok::<(), ovba::Error>(())
+//! # Ok(())
+//! # }
+//! # #[cfg(not(feature = "std"))]
+//! # fn main() {}
 //! ```
 //!
 //! The API also supports low-level access to the [\[MS-CFB\]: Compound File Binary File
 //! Format][MS-CFB] data. The following example lists all CFB entries:
 //!
 //! ```rust,no_run
+//! # #[cfg(feature = "std")]
+//! # fn main() -> ovba::Result<()> {
 //! use std::fs::read;
 //! use ovba::open_project;
 //!
@@ -47,49 +59,327 @@
 //! for (name, path) in &project.list()? {
 //!     println!(r#"Name: "{}"; Path: "{}""#, name, path);
 //! }
-//! # Ok::<(), ovba::Error>(())
+//! # Ok(())
+//! # }
+//! # #[cfg(not(feature = "std"))]
+//! # fn main() {}
 //! ```
 //!
+//! # Optional features
+//!
+//! * `std` (enabled by default): Adds [\[MS-CFB\]][MS-CFB] container access, including
+//!   [`Project`] and the `open_project` family of functions. Without it, the crate is
+//!   `no_std`, and requires `alloc` only. The public data types, [`parse_dir`],
+//!   [`DirBuilder`], [`compress`], [`decompress`], and [`iter_dir_records`] remain
+//!   available.
+//! * `serde`: Implements `Serialize` for the public data types, and `Deserialize` for
+//!   those that don't borrow from or refer to a CFB container. [`Project`] serializes its
+//!   `information`, `references`, and `modules`.
+//! * `rayon`: Adds `Project::sources_par`, which decompresses the source code of all
+//!   modules in parallel.
+//! * `zip`: Adds `Project::to_source_zip`, which bundles the source code of all modules
+//!   into a ZIP archive. Implies `serde`.
+//! * `log`: Emits `debug` and `trace` records through the [`log`][log] crate, tracing
+//!   the parser's decisions, such as the optional records encountered, the code page
+//!   used, and the REFERENCE variants matched. Nothing is logged at higher levels.
+//!
 //! [MS-OVBA]: https://docs.microsoft.com/en-us/openspecs/office_file_formats/ms-ovba/575462ba-bf67-4190-9fac-c275523c75fc
+//! [log]: https://docs.rs/log
 //! [MS-CFB]: https://docs.microsoft.com/en-us/openspecs/windows_protocols/ms-cfb/53989ce4-7b05-4f8d-829b-d08d6148375b
 
+#![cfg_attr(not(feature = "std"), no_std)]
 #![forbid(unsafe_code)]
 #![warn(rust_2018_idioms, missing_docs)]
 
+extern crate alloc;
+
+/// Emits a `debug` record if the `log` feature is enabled. Otherwise, the arguments are
+/// type-checked, but not evaluated.
+macro_rules! debug {
+    ($($arg:tt)+) => {
+        #[cfg(feature = "log")]
+        log::debug!($($arg)+);
+        #[cfg(not(feature = "log"))]
+        if false {
+            let _ = format_args!($($arg)+);
+        }
+    };
+}
+
+/// Emits a `trace` record if the `log` feature is enabled. See `debug!`.
+macro_rules! trace {
+    ($($arg:tt)+) => {
+        #[cfg(feature = "log")]
+        log::trace!($($arg)+);
+        #[cfg(not(feature = "log"))]
+        if false {
+            let _ = format_args!($($arg)+);
+        }
+    };
+}
+
+#[cfg(feature = "std")]
+mod attributes;
 mod error;
-pub use crate::error::{Error, Result};
+pub use crate::error::{Error, ParseErrorKind, Result};
+pub use crate::parser::compress;
+#[cfg(feature = "std")]
+pub use crate::parser::Decompressor;
 
 mod parser;
+#[cfg(feature = "std")]
+mod properties;
+mod writer;
+pub use crate::writer::DirBuilder;
 
+#[cfg(feature = "std")]
 use cfb::CompoundFile;
-use parser::cp_to_string;
+#[cfg(feature = "std")]
+use parser::{cp_into_string, cp_to_str};
+#[cfg(feature = "std")]
+use sha2::{Digest, Sha256};
 
+use alloc::{format, string::String, vec::Vec};
+use core::fmt;
+#[cfg(feature = "std")]
 use std::{
-    cell::RefCell,
-    io::{Cursor, Read},
-    path::{Path, PathBuf},
-    str::FromStr,
+    borrow::Cow,
+    cell::{Ref, RefCell, RefMut},
+    collections::HashMap,
+    io::{self, Cursor, Read, Seek, SeekFrom, Write},
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
 };
 
 /// Represents a VBA project.
 ///
 /// This type serves as the entry point into this crate's functionality and exposes the
 /// public API surface.
-pub struct Project {
+///
+/// `R` is the type of the reader that backs the CFB container. It defaults to an
+/// in-memory buffer, as used by [`open_project`]. Use [`open_project_from`] to open a
+/// project from any other reader.
+///
+/// A `Project` can be moved to another thread if `R` is `Send`. It cannot be shared
+/// between threads, as reading streams requires exclusive access to the container. Use
+/// [`Project::into_parts`] to separate the project's metadata from the container.
+#[cfg(feature = "std")]
+pub struct Project<R = Cursor<Vec<u8>>> {
     /// Specifies version-independent information for the VBA project.
     pub information: Information,
     /// Specifies the external references of the VBA project.
     pub references: Vec<Reference>,
     /// Specifies the modules in the project.
     pub modules: Vec<Module>,
-    // TODO: Figure out how to make this generic (attempts have failed with
-    //       trait bound violations). This would allow [`open_project`] to
-    //       accept a wider range of input types.
-    container: RefCell<CompoundFile<Cursor<Vec<u8>>>>,
+    /// The container, only `None` while [`Project::write_to`] copies its bytes.
+    container: RefCell<Option<CompoundFile<R>>>,
+    root: PathBuf,
+    options: OpenOptions,
+    warnings: Vec<Warning>,
+    /// The metadata stored in the container's *dir* stream, used to detect changes.
+    stored_metadata: ProjectMetadata,
+    source_cache: RefCell<HashMap<String, String>>,
+    stream_cache: RefCell<HashMap<PathBuf, Arc<Vec<u8>>>>,
+}
+
+/// Serializes a project's `information`, `references`, and `modules`.
+#[cfg(all(feature = "std", feature = "serde"))]
+impl<R> serde::Serialize for Project<R> {
+    fn serialize<S>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("Project", 3)?;
+        state.serialize_field("information", &self.information)?;
+        state.serialize_field("references", &self.references)?;
+        state.serialize_field("modules", &self.modules)?;
+        state.end()
+    }
+}
+
+/// Options and flags which can be used to configure how a VBA project is opened.
+///
+/// This builder exposes the ability to configure how a [`Project`] is opened and what
+/// deviations from the file format specification are tolerated. The [`open_project`]
+/// function is equivalent to `OpenOptions::new().open(raw)`.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # #[cfg(feature = "std")]
+/// # fn main() -> ovba::Result<()> {
+/// use std::fs::read;
+/// use ovba::OpenOptions;
+///
+/// let data = read("vbaProject.bin")?;
+/// let project = OpenOptions::new().forward_compat(true).open(data)?;
+/// for warning in project.warnings() {
+///     println!("{}", warning);
+/// }
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "std"))]
+/// # fn main() {}
+/// ```
+#[derive(Clone, Debug)]
+pub struct OpenOptions {
+    pub(crate) forward_compat: bool,
+    pub(crate) lenient: bool,
+    pub(crate) cache_sources: bool,
+    pub(crate) fallback_encoding: bool,
+    pub(crate) verify_duplicates: bool,
+    pub(crate) code_page: Option<u16>,
+    pub(crate) max_decompressed_size: usize,
+}
+
+impl Default for OpenOptions {
+    fn default() -> Self {
+        Self {
+            forward_compat: false,
+            lenient: false,
+            cache_sources: false,
+            fallback_encoding: false,
+            verify_duplicates: false,
+            code_page: None,
+            max_decompressed_size: OpenOptions::DEFAULT_MAX_DECOMPRESSED_SIZE,
+        }
+    }
+}
+
+/// A non-fatal diagnostic recorded while opening a VBA project.
+///
+/// Warnings are reported through [`Project::warnings`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum Warning {
+    /// An unknown record was skipped in forward-compatibility mode.
+    UnknownRecord {
+        /// The record's ID.
+        id: u16,
+        /// Offset of the record into the decompressed *dir* stream.
+        offset: usize,
+        /// Size of the record's payload.
+        size: usize,
+    },
+    /// The *dir* stream's final terminator record is missing or malformed.
+    MissingTerminator {
+        /// Offset into the decompressed *dir* stream where the terminator was expected.
+        offset: usize,
+    },
+    /// Data following the *dir* stream's final terminator record was ignored.
+    TrailingData {
+        /// Offset into the decompressed *dir* stream where the trailing data starts.
+        offset: usize,
+        /// Size of the trailing data.
+        size: usize,
+    },
+    /// A malformed reference was skipped in lenient mode.
+    SkippedReference {
+        /// Offset of the reference into the decompressed *dir* stream.
+        offset: usize,
+    },
+    /// The project's code page cannot be mapped to an encoding, and was replaced.
+    ///
+    /// All strings, including the source code of every module, are decoded using the
+    /// `fallback` code page. [`Information::code_page`] reports the fallback code page.
+    EncodingFallback {
+        /// Offset of the `PROJECTCODEPAGE` record into the decompressed *dir* stream.
+        offset: usize,
+        /// The code page stored in the project.
+        code_page: u16,
+        /// The code page used instead.
+        fallback: u16,
+    },
+    /// A record that duplicates another record's data doesn't match it.
+    ///
+    /// Such records are Unicode records that don't hold the UTF-16 encoding of their MBCS
+    /// counterparts, or a `PROJECTHELPFILEPATH` record with two different paths. Only
+    /// reported if enabled through [`OpenOptions::verify_duplicates`].
+    MismatchedDuplicate {
+        /// The ID of the duplicate record, e.g. `0x0047` for `MODULENAMEUNICODE`.
+        id: u16,
+        /// Offset of the duplicate record into the decompressed *dir* stream.
+        offset: usize,
+    },
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Warning::UnknownRecord { id, offset, size } => write!(
+                f,
+                "Skipped unknown record 0x{:04X} at offset 0x{:X} ({} bytes)",
+                id, offset, size
+            ),
+            Warning::MissingTerminator { offset } => {
+                write!(f, "Missing terminator at offset 0x{:X}", offset)
+            }
+            Warning::TrailingData { offset, size } => write!(
+                f,
+                "Ignored {} bytes of trailing data at offset 0x{:X}",
+                size, offset
+            ),
+            Warning::SkippedReference { offset } => {
+                write!(f, "Skipped malformed reference at offset 0x{:X}", offset)
+            }
+            Warning::EncodingFallback {
+                offset,
+                code_page,
+                fallback,
+            } => write!(
+                f,
+                "Code page {} at offset 0x{:X} cannot be mapped to an encoding; using code page {}",
+                code_page, offset, fallback
+            ),
+            Warning::MismatchedDuplicate { id, offset } => write!(
+                f,
+                "Record 0x{:04X} at offset 0x{:X} doesn't match the data it duplicates",
+                id, offset
+            ),
+        }
+    }
+}
+
+/// A milestone reached while opening a VBA project.
+///
+/// Passed to the observer of [`OpenOptions::open_with_observer`], in the order listed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParseEvent<'a> {
+    /// The *dir* stream was read and decompressed.
+    DirDecompressed {
+        /// The size of the decompressed *dir* stream.
+        len: usize,
+    },
+    /// The project's references were parsed.
+    ReferencesParsed {
+        /// The number of references.
+        count: usize,
+    },
+    /// A module's record was parsed. Reported once per module.
+    ModuleParsed {
+        /// The module's name.
+        name: &'a str,
+    },
+}
+
+/// A raw record of the *dir* stream, as returned by [`iter_dir_records`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DirRecord<'a> {
+    /// The record's ID.
+    pub id: u16,
+    /// The record's data, excluding the ID and size fields.
+    pub payload: &'a [u8],
 }
 
 /// Specifies the platform for which the VBA project is created.
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SysKind {
     /// For 16-bit Windows Platforms.
     Win16,
@@ -101,10 +391,9 @@ pub enum SysKind {
     Win64,
 }
 
-// TODO: Remove exemption once the implementation is complete.
-#[allow(dead_code)]
 /// Specifies a reference to a twiddled type library and its extended type library.
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ReferenceControl {
     /// (Optional) Name entry
     name: Option<String>,
@@ -112,36 +401,33 @@ pub struct ReferenceControl {
     libid_twiddled: String,
     name_extended: Option<String>,
     libid_extended: String,
-    guid: Vec<u8>, // Should be an `[u8; 16]`, though I'm not sure how to convert &[u8] returned by the parser into an array.
+    guid: [u8; 16],
     /// MUST be Unique for each `ReferenceControl` in the VBA projectwith the same
     /// libid_original.
     cookie: u32,
 }
 
-// TODO: Remove exemption once the implementation is complete.
-#[allow(dead_code)]
 /// Specifies the identifier of the Automation type library the containing
 /// [`ReferenceControl`]'s twiddled type library was generated from.
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ReferenceOriginal {
     /// (Optional) Name entry
     name: Option<String>,
     libid_original: String,
 }
 
-// TODO: Remove exemption once the implementation is complete.
-#[allow(dead_code)]
 /// Specifies a reference to an Automation type library.
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ReferenceRegistered {
     name: Option<String>,
     libid: String,
 }
 
-// TODO: Remove exemption once the implementation is complete.
-#[allow(dead_code)]
 /// Specifies a reference to an external VBA project.
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ReferenceProject {
     name: Option<String>,
     libid_absolute: String,
@@ -151,7 +437,8 @@ pub struct ReferenceProject {
 }
 
 /// Specifies a reference to an Automation type library or VBA project.
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Reference {
     /// The `Reference` is a [`ReferenceControl`].
     Control(ReferenceControl),
@@ -163,10 +450,187 @@ pub enum Reference {
     Project(ReferenceProject),
 }
 
-// TODO: Remove exemption once the implementation is complete.
-#[allow(dead_code)]
+impl ReferenceControl {
+    /// Returns the reference's name, if present.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns the libid of the Automation type library the twiddled type library was
+    /// generated from, if present.
+    pub fn libid_original(&self) -> Option<&str> {
+        self.libid_original.as_deref()
+    }
+
+    /// Returns the libid of the twiddled type library.
+    pub fn libid_twiddled(&self) -> &str {
+        &self.libid_twiddled
+    }
+
+    /// Returns the name of the extended type library, if present.
+    pub fn name_extended(&self) -> Option<&str> {
+        self.name_extended.as_deref()
+    }
+
+    /// Returns the libid of the extended type library.
+    ///
+    /// The libid is empty if the reference lacks the extended type library. The
+    /// [`guid`](ReferenceControl::guid) and [`cookie`](ReferenceControl::cookie) are
+    /// zero in that case.
+    pub fn libid_extended(&self) -> &str {
+        &self.libid_extended
+    }
+
+    /// Returns the GUID of the Automation type library the extended type library was
+    /// generated from.
+    ///
+    /// Use [`ReferenceControl::guid_string`] for the textual representation.
+    pub fn guid(&self) -> &[u8; 16] {
+        &self.guid
+    }
+
+    /// Returns the GUID formatted as a string, e.g.
+    /// `{0D452EE1-E08F-101A-852E-02608C4D0BB4}`.
+    ///
+    /// The first three fields of a GUID are stored in little-endian byte order.
+    pub fn guid_string(&self) -> String {
+        let g = &self.guid;
+        format!(
+            "{{{:08X}-{:04X}-{:04X}-{:02X}{:02X}-{:02X}{:02X}{:02X}{:02X}{:02X}{:02X}}}",
+            u32::from_le_bytes([g[0], g[1], g[2], g[3]]),
+            u16::from_le_bytes([g[4], g[5]]),
+            u16::from_le_bytes([g[6], g[7]]),
+            g[8],
+            g[9],
+            g[10],
+            g[11],
+            g[12],
+            g[13],
+            g[14],
+            g[15]
+        )
+    }
+
+    /// Returns the reference's cookie.
+    pub fn cookie(&self) -> u32 {
+        self.cookie
+    }
+}
+
+impl ReferenceOriginal {
+    /// Returns the reference's name, if present.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns the libid of the referenced Automation type library.
+    pub fn libid_original(&self) -> &str {
+        &self.libid_original
+    }
+}
+
+impl ReferenceRegistered {
+    /// Returns the reference's name, if present.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns the libid of the referenced Automation type library.
+    pub fn libid(&self) -> &str {
+        &self.libid
+    }
+}
+
+impl ReferenceProject {
+    /// Returns the reference's name, if present.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns the absolute path to the referenced VBA project.
+    pub fn libid_absolute(&self) -> &str {
+        &self.libid_absolute
+    }
+
+    /// Returns the path to the referenced VBA project, relative to the referencing
+    /// project.
+    ///
+    /// Use [`ReferenceProject::resolved_libid`] to resolve the path.
+    pub fn libid_relative(&self) -> &str {
+        &self.libid_relative
+    }
+
+    /// Returns the major version of the referenced VBA project.
+    pub fn major_version(&self) -> u32 {
+        self.major_version
+    }
+
+    /// Returns the minor version of the referenced VBA project.
+    pub fn minor_version(&self) -> u16 {
+        self.minor_version
+    }
+
+    /// Returns the path of the referenced project, resolved against `base`.
+    ///
+    /// The relative libid is a `ProjectReference` (see \[MS-OVBA\] section 2.1.1.12),
+    /// with a path relative to the referencing project's location. This function strips
+    /// the `*\` prefix and reference kind character (e.g. `*\A`) from the libid, and
+    /// joins the remaining path to `base`. Both `\` and `/` are treated as path
+    /// separators, irrespective of the target platform.
+    ///
+    /// `base` is commonly the directory containing the document that holds the VBA
+    /// project. The file system isn't accessed; the result may refer to a non-existent
+    /// file.
+    #[cfg(feature = "std")]
+    pub fn resolved_libid(&self, base: &Path) -> PathBuf {
+        let path =
+            self.libid_relative
+                .strip_prefix("*\\")
+                .map_or(self.libid_relative.as_str(), |libid| {
+                    let mut chars = libid.chars();
+                    chars.next();
+                    chars.as_str()
+                });
+
+        let mut result = base.to_path_buf();
+        for component in path.split(['\\', '/']) {
+            if !component.is_empty() {
+                result.push(component);
+            }
+        }
+        result
+    }
+}
+
+impl Reference {
+    /// Returns the reference's name, if present.
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            Reference::Control(reference) => reference.name(),
+            Reference::Original(reference) => reference.name(),
+            Reference::Registered(reference) => reference.name(),
+            Reference::Project(reference) => reference.name(),
+        }
+    }
+
+    /// Returns the reference's primary libid.
+    ///
+    /// This is the twiddled libid for a [`ReferenceControl`], and the absolute libid for
+    /// a [`ReferenceProject`].
+    #[cfg(feature = "std")]
+    fn libid(&self) -> &str {
+        match self {
+            Reference::Control(reference) => &reference.libid_twiddled,
+            Reference::Original(reference) => &reference.libid_original,
+            Reference::Registered(reference) => &reference.libid,
+            Reference::Project(reference) => &reference.libid_absolute,
+        }
+    }
+}
+
 /// Specifies version-independent information for the VBA project.
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Information {
     /// Specifies the platform for which the VBA project is created.
     pub sys_kind: SysKind,
@@ -186,8 +650,106 @@ pub struct Information {
     constants: Option<String>,
 }
 
+impl Information {
+    /// Creates the information for a new Win32 VBA project called `name`, using code page
+    /// 1252 and LCID 0x0409 (English - United States).
+    ///
+    /// All other values are empty or zero, except for the version, which is 1.0. This
+    /// allows writing new projects with a [`DirBuilder`].
+    pub fn new(name: &str) -> Self {
+        Self {
+            sys_kind: SysKind::Win32,
+            compat: None,
+            lcid: 0x0409,
+            lcid_invoke: 0x0409,
+            code_page: 1252,
+            name: String::from(name),
+            doc_string: String::new(),
+            help_file_1: String::new(),
+            help_context: 0,
+            lib_flags: 0,
+            version_major: 1,
+            version_minor: 0,
+            constants: None,
+        }
+    }
+
+    /// Returns the version of the VBA project compatibility information, if present.
+    ///
+    /// This is the value of the optional `PROJECTCOMPATVERSION` record.
+    pub fn compat_version(&self) -> Option<u32> {
+        self.compat
+    }
+
+    /// Returns the LCID of the VBA project.
+    pub fn lcid(&self) -> u32 {
+        self.lcid
+    }
+
+    /// Returns the LCID used when invoking methods on Automation servers.
+    pub fn lcid_invoke(&self) -> u32 {
+        self.lcid_invoke
+    }
+
+    /// Returns the name of the VBA project.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the description of the VBA project.
+    pub fn doc_string(&self) -> &str {
+        &self.doc_string
+    }
+
+    /// Returns the path to the Help file for the VBA project.
+    pub fn help_file(&self) -> &str {
+        &self.help_file_1
+    }
+
+    /// Returns the Help topic identifier of the VBA project.
+    pub fn help_context(&self) -> u32 {
+        self.help_context
+    }
+
+    /// Returns the `LIBFLAGS` of the VBA project's type library.
+    pub fn lib_flags(&self) -> u32 {
+        self.lib_flags
+    }
+
+    /// Returns the major version of the VBA project.
+    pub fn version_major(&self) -> u32 {
+        self.version_major
+    }
+
+    /// Returns the minor version of the VBA project.
+    pub fn version_minor(&self) -> u16 {
+        self.version_minor
+    }
+
+    /// Returns the version of the VBA project as a `(major, minor)` tuple.
+    ///
+    /// Tuples compare lexicographically, which allows comparing versions directly.
+    pub fn version(&self) -> (u32, u16) {
+        (self.version_major, self.version_minor)
+    }
+
+    /// Returns the version of the VBA project formatted as `major.minor`.
+    pub fn version_string(&self) -> String {
+        format!("{}.{}", self.version_major, self.version_minor)
+    }
+
+    /// Returns the compilation constants of the VBA project.
+    ///
+    /// The constants are formatted as a `:`-separated list of `name = value` pairs.
+    /// An empty string is returned if the project doesn't declare any constants.
+    pub fn constants(&self) -> &str {
+        self.constants.as_deref().unwrap_or_default()
+    }
+}
+
 /// Specifies the containing module's type.
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ModuleType {
     /// Specifies a procedural module.
     ///
@@ -211,8 +773,55 @@ pub enum ModuleType {
     DocClsDesigner,
 }
 
+impl ModuleType {
+    /// Returns the file extension (without a leading `.`) conventionally used when
+    /// exporting a module of this type: `bas` for procedural modules, and `cls`
+    /// otherwise.
+    ///
+    /// Designer modules are exported as `frm` files by Office. Use
+    /// [`ModuleKind::extension`] to tell them apart.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ModuleType::Procedural => "bas",
+            ModuleType::DocClsDesigner => "cls",
+        }
+    }
+}
+
+/// Specifies a module's kind.
+///
+/// Unlike [`ModuleType`], this distinguishes document modules, class modules, and
+/// designer modules. This information isn't stored in the *dir* stream, and is
+/// recovered from the *PROJECT* stream instead. See [`Project::module_kind`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ModuleKind {
+    /// A procedural module.
+    Procedural,
+    /// A document module, e.g. `ThisWorkbook` or `Sheet1`.
+    Document,
+    /// A class module.
+    Class,
+    /// A designer module, e.g. a `UserForm`.
+    Designer,
+}
+
+impl ModuleKind {
+    /// Returns the file extension (without a leading `.`) conventionally used when
+    /// exporting a module of this kind: `bas` for procedural modules, `cls` for document
+    /// and class modules, and `frm` for designer modules.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ModuleKind::Procedural => "bas",
+            ModuleKind::Document | ModuleKind::Class => "cls",
+            ModuleKind::Designer => "frm",
+        }
+    }
+}
+
 /// Specifies data for a module.
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Module {
     /// Specifies a VBA identifier as the name of the containing `Module`.
     pub name: String,
@@ -234,9 +843,399 @@ pub struct Module {
     /// Specifies that the containing `Module` is only usable from within the current VBA
     /// project.
     pub private: bool,
+    cookie: u16,
+}
+
+impl Module {
+    /// Creates a module called `name`, stored in a stream of the same name.
+    ///
+    /// The module's source code starts at the beginning of its stream, i.e. it has no
+    /// performance cache. Its cookie holds `0xFFFF`, as mandated for writers.
+    pub fn new(name: &str, module_type: ModuleType) -> Self {
+        Self {
+            name: String::from(name),
+            stream_name: String::from(name),
+            doc_string: String::new(),
+            text_offset: 0,
+            help_context: 0,
+            module_type,
+            read_only: false,
+            private: false,
+            cookie: 0xffff,
+        }
+    }
+
+    /// Returns the value of the module's `MODULECOOKIE` record.
+    ///
+    /// The specification requires readers to ignore this value, and writers to store
+    /// `0xFFFF`. Other values can hint at the application that last saved the project.
+    pub fn cookie(&self) -> u16 {
+        self.cookie
+    }
+
+    /// Returns the attributes declared at the start of the module's source code.
+    ///
+    /// This reads and decodes the module's source code from `project`, and interprets the
+    /// attributes returned by [`Project::module_attributes`].
+    #[cfg(feature = "std")]
+    pub fn attributes<R: Read + Seek>(&self, project: &Project<R>) -> Result<ModuleAttributes> {
+        let attributes = project.module_attributes(&self.name)?;
+        Ok(attributes::module_attributes(attributes))
+    }
+
+    /// Returns the logical lines of the module's source code.
+    ///
+    /// This reads and decodes the module's source code from `project`. See
+    /// [`SourceLines`] for how it is split into lines.
+    #[cfg(feature = "std")]
+    pub fn source_lines<R: Read + Seek>(&self, project: &Project<R>) -> Result<SourceLines> {
+        let source = project.source_of(self)?;
+        Ok(SourceLines { source })
+    }
+}
+
+/// A module's source code, split into logical lines.
+///
+/// Returned by [`Module::source_lines`]. Physical lines are terminated by `"\r\n"`.
+/// Carriage returns and line feeds that don't form a line terminator are part of the
+/// line. A line ending in the ` _` line continuation sequence is joined with the
+/// following line: The underscore, the line terminator, and the following line's
+/// leading whitespace are dropped.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SourceLines {
+    source: String,
+}
+
+#[cfg(feature = "std")]
+impl SourceLines {
+    /// Returns an iterator over the logical lines, excluding line terminators.
+    ///
+    /// Lines are borrowed from the source code, except for joined lines.
+    pub fn iter(&self) -> impl Iterator<Item = Cow<'_, str>> {
+        attributes::logical_lines(&self.source)
+    }
+
+    /// Returns the module's source code, as decoded.
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+}
+
+/// Specifies the attributes declared at the start of a module's source code.
+///
+/// Returned by [`Module::attributes`]. Flag attributes that aren't declared are `false`.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ModuleAttributes {
+    /// The module's name (`VB_Name`).
+    pub name: Option<String>,
+    /// The class identifier of the module's base class (`VB_Base`).
+    pub base: Option<String>,
+    /// Whether the class's members are accessible without qualification
+    /// (`VB_GlobalNameSpace`).
+    pub global_name_space: bool,
+    /// Whether the class can be instantiated from outside the project (`VB_Creatable`).
+    pub creatable: bool,
+    /// Whether a default instance of the class is created (`VB_PredeclaredId`).
+    pub predeclared_id: bool,
+    /// Whether the class is visible outside the project (`VB_Exposed`).
+    pub exposed: bool,
+    /// Whether the class can be customized (`VB_Customizable`).
+    pub customizable: bool,
+    /// Any other attributes, including flag attributes with a value other than `True` or
+    /// `False`, keyed by name.
+    pub other: HashMap<String, String>,
+}
+
+/// The differences between two VBA projects.
+///
+/// Returned by [`Project::diff`]. Modules are matched by name, ignoring ASCII case.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProjectDiff {
+    /// Whether the projects' [`Information`] differs.
+    pub information_changed: bool,
+    /// The names of modules only present in the other project.
+    pub added_modules: Vec<String>,
+    /// The names of modules only present in this project.
+    pub removed_modules: Vec<String>,
+    /// The modules present in both projects that differ.
+    pub modified_modules: Vec<ModuleDiff>,
+    /// References only present in the other project.
+    pub added_references: Vec<Reference>,
+    /// References only present in this project.
+    pub removed_references: Vec<Reference>,
+}
+
+impl ProjectDiff {
+    /// Returns `true` if the projects don't differ.
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+/// The differences between two versions of a module.
+///
+/// Part of a [`ProjectDiff`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ModuleDiff {
+    /// The module's name, as stored in this project.
+    pub name: String,
+    /// Whether the module's metadata (other than [`Module::text_offset`] and
+    /// [`Module::cookie`]) differs.
+    pub metadata_changed: bool,
+    /// The module's source code in this and the other project, if it differs.
+    pub source: Option<(String, String)>,
+}
+
+/// An owned snapshot of a VBA project's metadata, detached from the CFB container.
+///
+/// Returned by [`Project::clone_metadata`] and [`parse_dir`]. Unlike a [`Project`], this
+/// doesn't hold on to the underlying reader (such as an open file), but cannot be used to
+/// read streams.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProjectMetadata {
+    /// Specifies version-independent information for the VBA project.
+    pub information: Information,
+    /// Specifies the external references of the VBA project.
+    pub references: Vec<Reference>,
+    /// Specifies the modules in the project.
+    pub modules: Vec<Module>,
+}
+
+/// Describes the relationship between a project's compiled code and its source code.
+///
+/// Returned by [`Project::source_cache_consistency`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum CacheStatus {
+    /// No compiled code is present. The source code is what executes.
+    SourceOnly,
+    /// Compiled code is present alongside the source code. The source code may not
+    /// reflect what executes.
+    CachePresent,
+    /// Compiled code is present for the listed modules, while their source code is empty
+    /// (apart from `Attribute` lines). This is indicative of VBA stomping.
+    SourceMissing(Vec<String>),
+}
+
+/// Describes an entry (a storage or a stream) in a project's CFB container.
+///
+/// Returned by [`Project::entries`].
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Entry {
+    /// The entry's name.
+    pub name: String,
+    /// The entry's path inside the CFB.
+    pub path: String,
+    /// Whether the entry is a stream (as opposed to a storage).
+    pub is_stream: bool,
+    /// The size of a stream, in bytes. This is `0` for storages.
+    pub len: u64,
+    /// The creation time of a storage. Streams don't record timestamps, and report the
+    /// CFB epoch (January 1, 1601) instead, where representable.
+    pub created: SystemTime,
+    /// The modification time of a storage. Streams don't record timestamps, see
+    /// `created`.
+    pub modified: SystemTime,
+    /// The class identifier (CLSID) of a storage, including braces. This is the nil GUID
+    /// for streams, and for storages without a CLSID.
+    pub clsid: String,
+}
+
+/// A human-readable overview of a VBA project.
+///
+/// Returned by [`Project::summary`]. The `Display` implementation renders the summary as
+/// a block of text, with one property per line.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProjectSummary {
+    /// The project's name.
+    pub name: String,
+    /// The platform for which the project was created.
+    pub sys_kind: SysKind,
+    /// The project's code page.
+    pub code_page: u16,
+    /// The VBA version stored in the *_VBA_PROJECT* stream, or `None` if the stream
+    /// cannot be read.
+    pub vba_version: Option<u16>,
+    /// The number of procedural modules.
+    pub procedural_modules: usize,
+    /// The number of document, class, and designer modules.
+    pub doc_cls_designer_modules: usize,
+    /// The primary libids of the project's references.
+    pub references: Vec<String>,
+}
+
+impl fmt::Display for ProjectSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Name:        {}", self.name)?;
+        writeln!(f, "Platform:    {:?}", self.sys_kind)?;
+        writeln!(f, "Code page:   {}", self.code_page)?;
+        match self.vba_version {
+            Some(version) => writeln!(f, "VBA version: 0x{:04X}", version)?,
+            None => writeln!(f, "VBA version: unknown")?,
+        }
+        writeln!(
+            f,
+            "Modules:     {} ({} procedural, {} document/class/designer)",
+            self.procedural_modules + self.doc_cls_designer_modules,
+            self.procedural_modules,
+            self.doc_cls_designer_modules
+        )?;
+        write!(f, "References:  {}", self.references.len())?;
+        for libid in &self.references {
+            write!(f, "\n  {}", libid)?;
+        }
+        Ok(())
+    }
 }
 
+/// Specifies the properties stored in the *PROJECT* stream.
+///
+/// The *PROJECT* stream holds the project's properties as human-readable text. Some of
+/// this information duplicates the *dir* stream, but the stream is authoritative for the
+/// order in which modules are loaded, and additionally lists the host extenders.
+///
+/// Returned by [`Project::project_properties`].
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProjectProperties {
+    /// Specifies the class identifier (GUID) of the VBA project, including braces.
+    pub id: Option<String>,
+    /// Specifies the name of the VBA project.
+    pub name: Option<String>,
+    /// Specifies the path of the Help file associated with the VBA project.
+    pub help_file: Option<String>,
+    /// Specifies the Help topic identifier in the Help file.
+    pub help_context_id: Option<u32>,
+    /// Specifies the names of the project's modules (of all module types), in the order
+    /// they are listed in the stream.
+    pub modules: Vec<String>,
+    /// Specifies the host extenders listed in the `[Host Extender Info]` section.
+    pub host_extenders: Vec<HostExtender>,
+    /// Specifies the encrypted protection state (the `CMG` property), as stored.
+    pub protection_state: Option<String>,
+    /// Specifies the encrypted password (the `DPB` property), as stored.
+    pub password: Option<String>,
+    /// Specifies the encrypted visibility state (the `GC` property), as stored.
+    pub visibility_state: Option<String>,
+    /// The kinds of the modules in `modules`, in the same order.
+    module_kinds: Vec<ModuleKind>,
+}
+
+impl ProjectProperties {
+    /// Returns the kind of the module called `name`, as declared by the line that lists
+    /// the module, or `None` if the module isn't listed.
+    pub fn module_kind(&self, name: &str) -> Option<ModuleKind> {
+        self.modules
+            .iter()
+            .position(|module| module == name)
+            .map(|index| self.module_kinds[index])
+    }
+}
+
+/// Specifies the protection state of a VBA project.
+///
+/// Returned by [`Project::protection`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProtectionState {
+    /// Specifies whether access to the VBA project was restricted by the user.
+    pub locked: bool,
+    /// Specifies whether access to the VBA project was restricted by the host application.
+    pub host_protected: bool,
+    /// Specifies whether access to the VBA project was restricted by the VBA environment.
+    pub vbe_protected: bool,
+    /// Specifies whether the project is locked for viewing, i.e. its source code isn't
+    /// displayed in the VBA environment.
+    pub view_protected: bool,
+    /// Specifies whether the project has a password. The password is stored either as a
+    /// hash, or in plain text.
+    pub password_protected: bool,
+}
+
+/// Specifies a reference to an aggregatable double module (a host extender).
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HostExtender {
+    /// Specifies the index of the host extender entry.
+    pub index: u32,
+    /// Specifies the GUID of the host extender's type library, including braces.
+    pub guid: String,
+    /// Specifies the name of the host extender's type library, e.g. `VBE`.
+    pub lib_name: String,
+    /// Specifies the host extender's flags.
+    pub flags: u32,
+}
+
+#[cfg(feature = "std")]
 impl Project {
+    /// Opens the VBA project stored in the file at `path`.
+    ///
+    /// The file is read into memory in its entirety, and passed to [`open_project`].
+    /// Use [`OpenOptions`] to control how the project is opened.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use ovba::Project;
+    ///
+    /// let project = Project::open("vbaProject.bin")?;
+    /// # Ok::<(), ovba::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error::Io`] error if the file cannot be read.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Project> {
+        let raw = std::fs::read(path)?;
+        open_project(raw)
+    }
+
+    /// Returns the root paths of all VBA projects in a CFB container.
+    ///
+    /// A VBA project is identified by a *VBA* storage that contains a *dir* stream. The
+    /// returned root path is the path of the storage that contains the *VBA* storage,
+    /// e.g. `/` for a standalone *vbaProject.bin* or `/Macros` for a Word binary
+    /// document. Storage and stream names are compared case-insensitively.
+    ///
+    /// Crafted documents can contain more than one VBA project. This function allows
+    /// client code to discover all of them.
+    pub fn discover_projects<F>(container: &CompoundFile<F>) -> Result<Vec<String>>
+    where
+        F: Read + Seek,
+    {
+        let mut result = Vec::new();
+        for entry in container.walk() {
+            if !entry.is_stream() || !entry.name().eq_ignore_ascii_case("dir") {
+                continue;
+            }
+            let vba_storage = match entry.path().parent() {
+                Some(path) => path,
+                None => continue,
+            };
+            let is_vba_storage = matches!(
+                vba_storage.file_name().and_then(|name| name.to_str()),
+                Some(name) if name.eq_ignore_ascii_case("VBA")
+            );
+            if let (true, Some(root)) = (is_vba_storage, vba_storage.parent()) {
+                result.push(root.to_string_lossy().into_owned());
+            }
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read + Seek> Project<R> {
     /// Returns a stream's decompressed data.
     ///
     /// This function reads a stream referenced by `stream_path` and passes the data
@@ -253,11 +1252,31 @@ impl Project {
     where
         P: AsRef<Path>,
     {
-        let data = self.read_stream(stream_path)?;
-        let data = parser::decompress(&data[offset..])
-            .map_err(|_| Error::Decompressor)?
-            .1;
-        Ok(data)
+        let mut buffer = Vec::new();
+        self.decompress_stream_into(stream_path, offset, &mut buffer)?;
+        Ok(buffer)
+    }
+
+    /// Decompresses a stream into a caller-supplied buffer.
+    ///
+    /// This is the equivalent of [`Project::decompress_stream_from`], writing the
+    /// decompressed data to `buffer`. The buffer is cleared first, and its allocation is
+    /// reused. This allows amortizing allocations when decompressing many streams.
+    ///
+    /// The compressed data is read into a temporary buffer, unless the project was opened
+    /// with [`OpenOptions::cache_sources`] enabled. On failure, the contents of `buffer`
+    /// are unspecified.
+    pub fn decompress_stream_into<P>(
+        &self,
+        stream_path: P,
+        offset: usize,
+        buffer: &mut Vec<u8>,
+    ) -> Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        let data = self.stream(stream_path.as_ref())?;
+        decompress_from(&data, offset, buffer, self.options.max_decompressed_size)
     }
 
     // TODO: This should probably live someplace else. It exposes information internal to
@@ -269,35 +1288,439 @@ impl Project {
     /// contains the entry's name and the second element the entry's path inside the
     /// CFB.
     ///
-    /// The raw binary data is encoded as a [Compound File Binary][MS-CFB]
+    /// The raw binary data is encoded as a [Compound File Binary][MS-CFB]. Use
+    /// [`Project::entries`] to additionally retrieve sizes and timestamps.
     ///
     /// [MS-CFB]: https://docs.microsoft.com/en-us/openspecs/windows_protocols/ms-cfb/53989ce4-7b05-4f8d-829b-d08d6148375b
     pub fn list(&self) -> Result<Vec<(String, String)>> {
-        let mut result = Vec::new();
-        for entry in self
-            .container
-            .borrow()
-            .walk_storage("/")
-            .map_err(Error::Cfb)?
-        {
-            result.push((
-                entry.name().to_owned(),
-                entry.path().to_str().unwrap_or_default().to_owned(),
-            ));
-        }
+        let result = self
+            .entries()?
+            .into_iter()
+            .map(|entry| (entry.name, entry.path))
+            .collect();
         Ok(result)
     }
 
-    /// Returns a module's source code.
+    /// Returns the entries (storages and streams) in the raw binary data, including the
+    /// root storage.
+    ///
+    /// Entries are returned in the same order as [`Project::list`]. Each entry carries the
+    /// metadata stored in the CFB directory, such as the size of streams, and the
+    /// timestamps and CLSID of storages.
+    pub fn entries(&self) -> Result<Vec<Entry>> {
+        let result = self
+            .container()
+            .walk_storage("/")
+            .map_err(Error::Cfb)?
+            .map(|entry| Entry {
+                name: entry.name().to_owned(),
+                path: entry.path().to_str().unwrap_or_default().to_owned(),
+                is_stream: entry.is_stream(),
+                len: entry.len(),
+                created: entry.created(),
+                modified: entry.modified(),
+                clsid: format!("{{{}}}", entry.clsid()).to_uppercase(),
+            })
+            .collect();
+        Ok(result)
+    }
+
+    /// Returns an iterator over the project's references of type [`ReferenceRegistered`].
+    pub fn registered_references(&self) -> impl Iterator<Item = &ReferenceRegistered> {
+        self.references
+            .iter()
+            .filter_map(|reference| match reference {
+                Reference::Registered(registered) => Some(registered),
+                _ => None,
+            })
+    }
+
+    /// Returns an iterator over the project's references of type [`ReferenceProject`].
+    pub fn project_references(&self) -> impl Iterator<Item = &ReferenceProject> {
+        self.references
+            .iter()
+            .filter_map(|reference| match reference {
+                Reference::Project(project) => Some(project),
+                _ => None,
+            })
+    }
+
+    /// Returns an iterator over the project's references of type [`ReferenceControl`].
+    pub fn control_references(&self) -> impl Iterator<Item = &ReferenceControl> {
+        self.references
+            .iter()
+            .filter_map(|reference| match reference {
+                Reference::Control(control) => Some(control),
+                _ => None,
+            })
+    }
+
+    /// Returns an iterator over the names of all modules in the project.
+    ///
+    /// The names are VBA identifiers, as reported by [`Module::name`].
+    pub fn module_names(&self) -> impl Iterator<Item = &str> {
+        self.modules.iter().map(|module| module.name.as_str())
+    }
+
+    /// Returns an iterator over the stream names of all modules in the project.
+    ///
+    /// Stream names identify the CFB streams in the VBA storage that hold the modules'
+    /// source code, as reported by [`Module::stream_name`]. They can differ from the
+    /// module names, and correspond to the names returned by [`Project::list`].
+    pub fn module_stream_names(&self) -> impl Iterator<Item = &str> {
+        self.modules
+            .iter()
+            .map(|module| module.stream_name.as_str())
+    }
+
+    /// Returns a checksum over the project's content.
+    ///
+    /// The checksum identifies the source code and references of a project, independent
+    /// of the CFB layout. Two projects with identical modules and references produce the
+    /// same checksum, even when embedded in different documents.
+    ///
+    /// The checksum is the SHA-256 digest of the following canonical representation,
+    /// where every string is encoded as its UTF-8 byte length (`u64`, little endian)
+    /// followed by its UTF-8 bytes, and every count is a `u64` (little endian):
+    ///
+    /// 1. The number of modules.
+    /// 1. For each module: The module name, followed by the module's source code as
+    ///    returned by [`Project::module_source`]. Modules are ordered by name, comparing
+    ///    the names' UTF-8 bytes (i.e. case-sensitively, with `"B"` ordered before
+    ///    `"a"`). Modules with equal names remain in declaration order.
+    /// 1. The number of references.
+    /// 1. For each reference, in declaration order: A single byte identifying the
+    ///    variant, followed by the variant's libids:
+    ///    * `0x01` ([`Reference::Control`]): The original libid (empty if absent), the
+    ///      twiddled libid, and the extended libid.
+    ///    * `0x02` ([`Reference::Original`]): The original libid.
+    ///    * `0x03` ([`Reference::Registered`]): The libid.
+    ///    * `0x04` ([`Reference::Project`]): The absolute libid, and the relative libid.
+    ///
+    /// Reference names, module metadata, and project information don't contribute to
+    /// the checksum.
+    pub fn content_checksum(&self) -> Result<[u8; 32]> {
+        fn update_str(hasher: &mut Sha256, value: &str) {
+            hasher.update((value.len() as u64).to_le_bytes());
+            hasher.update(value.as_bytes());
+        }
+
+        let mut hasher = Sha256::new();
+
+        let mut modules = self.modules.iter().collect::<Vec<_>>();
+        modules.sort_by(|lhs, rhs| lhs.name.cmp(&rhs.name));
+        hasher.update((modules.len() as u64).to_le_bytes());
+        for module in modules {
+            update_str(&mut hasher, &module.name);
+            update_str(&mut hasher, &self.source_of(module)?);
+        }
+
+        hasher.update((self.references.len() as u64).to_le_bytes());
+        for reference in &self.references {
+            match reference {
+                Reference::Control(control) => {
+                    hasher.update([0x01]);
+                    update_str(
+                        &mut hasher,
+                        control.libid_original.as_deref().unwrap_or_default(),
+                    );
+                    update_str(&mut hasher, &control.libid_twiddled);
+                    update_str(&mut hasher, &control.libid_extended);
+                }
+                Reference::Original(original) => {
+                    hasher.update([0x02]);
+                    update_str(&mut hasher, &original.libid_original);
+                }
+                Reference::Registered(registered) => {
+                    hasher.update([0x03]);
+                    update_str(&mut hasher, &registered.libid);
+                }
+                Reference::Project(project) => {
+                    hasher.update([0x04]);
+                    update_str(&mut hasher, &project.libid_absolute);
+                    update_str(&mut hasher, &project.libid_relative);
+                }
+            }
+        }
+
+        Ok(hasher.finalize().into())
+    }
+
+    /// Returns an iterator over the references' primary libids.
+    ///
+    /// The iterator yields exactly one libid per reference, in the order of
+    /// [`Project::references`]. A libid is the twiddled libid for a
+    /// [`ReferenceControl`], and the absolute libid for a [`ReferenceProject`].
+    ///
+    /// Empty libids are valid, and are yielded as empty strings rather than skipped.
+    pub fn reference_libids(&self) -> impl Iterator<Item = &str> {
+        self.references.iter().map(Reference::libid)
+    }
+
+    /// Returns the first reference whose name matches `name`.
+    ///
+    /// Names are compared case-sensitively. References without a name never match.
+    pub fn reference_by_name(&self, name: &str) -> Option<&Reference> {
+        self.references
+            .iter()
+            .find(|&reference| reference.name() == Some(name))
+    }
+
+    /// Returns the first [`ReferenceControl`] whose extended type library GUID matches
+    /// `guid`.
+    ///
+    /// GUIDs are compared in their binary representation, as stored in the file. Other
+    /// reference variants don't carry a GUID, and never match.
+    pub fn reference_by_guid(&self, guid: &[u8; 16]) -> Option<&Reference> {
+        self.references.iter().find(|&reference| match reference {
+            Reference::Control(control) => &control.guid == guid,
+            _ => false,
+        })
+    }
+
+    /// Returns whether the project contains a module called `name`.
+    ///
+    /// Module names are matched the same way as by [`Project::module_source`], i.e.
+    /// case-insensitively (see [`Project::find_module`]).
+    pub fn module_exists(&self, name: &str) -> bool {
+        self.find_module(name).is_some()
+    }
+
+    /// Returns the module called `name`.
+    ///
+    /// VBA identifiers are case-insensitive, so module names are compared ignoring the
+    /// case of ASCII characters, e.g. `module1` finds a module called `Module1`. This is
+    /// how all functions taking a module name look up modules.
+    pub fn find_module(&self, name: &str) -> Option<&Module> {
+        self.modules
+            .iter()
+            .find(|module| module.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Returns a module's source code.
     ///
     /// Similar to [`Project::module_source_raw`] this function returns the source code
     /// of a project's module. After the raw source code has been decoded it is then
     /// converted to a `String` using the project's code page.
+    ///
+    /// Embedded NUL bytes are preserved, and decode to U+0000 characters. Use
+    /// [`Project::module_source_no_nul`] if the result is passed to code that expects
+    /// C string semantics.
+    ///
+    /// If the project was opened with [`OpenOptions::cache_sources`] enabled, the source
+    /// code is decompressed and decoded on first access only.
     pub fn module_source(&self, name: &str) -> Result<String> {
-        let source_raw = self.module_source_raw(name)?;
-        let source = cp_to_string(&source_raw, self.information.code_page);
+        self.source_of(self.module(name)?)
+    }
 
-        Ok(source)
+    /// Returns the modules' source code, in the order of `modules`.
+    ///
+    /// Each item holds a module along with its source code, as returned by
+    /// [`Project::module_source`]. Source code is read lazily, as the iterator advances.
+    /// Failure to read a module's source code is reported for that module only.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use ovba::Project;
+    ///
+    /// let project = Project::open("vbaProject.bin")?;
+    /// for source in project.sources() {
+    ///     let (module, source) = source?;
+    ///     println!("{}: {} characters", module.name, source.len());
+    /// }
+    /// # Ok::<(), ovba::Error>(())
+    /// ```
+    pub fn sources(&self) -> impl Iterator<Item = Result<(&Module, String)>> {
+        self.modules
+            .iter()
+            .map(move |module| Ok((module, self.source_of(module)?)))
+    }
+
+    /// Returns a ZIP archive holding the source code of all modules.
+    ///
+    /// Each module is stored as `src/{name}.{ext}`, with its source code as returned by
+    /// [`Project::module_source_clean`], encoded as UTF-8. Path separators (`/` and `\`)
+    /// and control characters in the module's name are replaced with `_`, so that every
+    /// file is stored in the `src` directory. The extension follows the module's
+    /// [`ModuleKind`] (see [`ModuleKind::extension`]), as read from the *PROJECT* stream.
+    /// If that stream is missing or doesn't list the module, [`ModuleType::extension`]
+    /// is used. Additionally, `project.json` holds the project's `information` and
+    /// `references`, serialized as JSON.
+    ///
+    /// The archive is assembled in memory. Failure to read any module fails the entire
+    /// operation.
+    ///
+    /// This function is only available with the `zip` feature enabled.
+    #[cfg(feature = "zip")]
+    pub fn to_source_zip(&self) -> Result<Vec<u8>> {
+        use zip::{write::SimpleFileOptions, ZipWriter};
+
+        let mut archive = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default();
+
+        // Module names are read from the *dir* stream, and cannot be trusted to be valid
+        // file names.
+        let file_name = |name: &str| -> String {
+            name.chars()
+                .map(|c| match c {
+                    '/' | '\\' => '_',
+                    c if c.is_control() => '_',
+                    c => c,
+                })
+                .collect()
+        };
+        let properties = self.project_properties().ok();
+
+        for module in &self.modules {
+            let kind = match module.module_type {
+                ModuleType::Procedural => None,
+                ModuleType::DocClsDesigner => properties
+                    .as_ref()
+                    .and_then(|properties| properties.module_kind(&module.name)),
+            };
+            let extension = kind.map_or(module.module_type.extension(), |kind| kind.extension());
+            let source = self.module_source_clean(&module.name)?;
+            let path = format!("src/{}.{}", file_name(&module.name), extension);
+            archive.start_file(path, options).map_err(io::Error::from)?;
+            archive.write_all(source.as_bytes())?;
+        }
+
+        #[derive(serde::Serialize)]
+        struct Manifest<'a> {
+            information: &'a Information,
+            references: &'a [Reference],
+        }
+        let manifest = Manifest {
+            information: &self.information,
+            references: &self.references,
+        };
+        archive
+            .start_file("project.json", options)
+            .map_err(io::Error::from)?;
+        serde_json::to_writer_pretty(&mut archive, &manifest).map_err(io::Error::from)?;
+
+        let archive = archive.finish().map_err(io::Error::from)?;
+        Ok(archive.into_inner())
+    }
+
+    /// Returns the source code of all modules, decompressed in parallel.
+    ///
+    /// Each item holds a module's name along with its source code, as returned by
+    /// [`Project::module_source`], in the order of `modules`. Failure to read a module's
+    /// source code is reported for that module only.
+    ///
+    /// Module streams are read from the container sequentially, and then decompressed and
+    /// decoded on the rayon thread pool. The source cache enabled through
+    /// [`OpenOptions::cache_sources`] isn't used.
+    ///
+    /// This function is only available with the `rayon` feature enabled.
+    #[cfg(feature = "rayon")]
+    pub fn sources_par(&self) -> Vec<Result<(String, String)>> {
+        use rayon::prelude::*;
+
+        let streams = self
+            .modules
+            .iter()
+            .map(|module| {
+                let path = module_stream_path(&self.root, &module.stream_name)?;
+                self.stream(&path)
+                    .map_err(|e| Error::module(&module.name, e))
+            })
+            .collect::<Vec<_>>();
+
+        let code_page = self.information.code_page;
+        let max_output = self.options.max_decompressed_size;
+        self.modules
+            .par_iter()
+            .zip(streams)
+            .map(|(module, data)| {
+                let mut source_raw = Vec::new();
+                decompress_from(&data?, module.text_offset, &mut source_raw, max_output)
+                    .map_err(|e| Error::module(&module.name, e))?;
+                let source = cp_into_string(source_raw, code_page)?;
+                Ok((module.name.clone(), source))
+            })
+            .collect()
+    }
+
+    /// Returns a module's source code with `"\n"` line terminators.
+    ///
+    /// This function returns the same source code as [`Project::module_source`], with
+    /// every `"\r\n"` line terminator replaced by `"\n"`. Carriage returns that don't
+    /// precede a line feed are preserved.
+    pub fn module_source_normalized(&self, name: &str) -> Result<String> {
+        let source = self.module_source(name)?;
+        Ok(source.replace("\r\n", "\n"))
+    }
+
+    /// Returns a module's source code with trailing whitespace removed.
+    ///
+    /// This function returns the same source code as [`Project::module_source`], with
+    /// trailing whitespace (including stray carriage returns) removed from every line,
+    /// and trailing empty lines removed altogether. Lines are terminated with `"\r\n"`,
+    /// the line terminator used by VBA.
+    ///
+    /// The result is intended for producing clean, diff-friendly source code dumps. Use
+    /// [`Project::module_source`] for a faithful representation.
+    pub fn module_source_trimmed(&self, name: &str) -> Result<String> {
+        let source = self.module_source(name)?;
+        let mut lines = source.lines().map(str::trim_end).collect::<Vec<_>>();
+        while lines.last() == Some(&"") {
+            lines.pop();
+        }
+
+        let mut result = String::with_capacity(source.len());
+        for line in lines {
+            result.push_str(line);
+            result.push_str("\r\n");
+        }
+        Ok(result)
+    }
+
+    /// Returns the attributes declared at the start of a module's source code.
+    ///
+    /// Attributes are returned as `(name, value)` pairs in declaration order, e.g.
+    /// `("VB_Name", "Module1")`. Quoted values are unquoted.
+    ///
+    /// Both the Office VBA and the VB6 source code layout are supported. VB6 source code
+    /// can start with a file header (a `VERSION` line, and a `BEGIN`/`END` block) ahead
+    /// of the `Attribute` lines, that is skipped. Keywords are matched
+    /// case-insensitively, and attribute statements can span multiple lines using line
+    /// continuations (` _`).
+    pub fn module_attributes(&self, name: &str) -> Result<Vec<(String, String)>> {
+        let source = self.module_source(name)?;
+        let (attributes, _) = attributes::split_attributes(&source);
+        Ok(attributes)
+    }
+
+    /// Returns a module's source code without the leading `Attribute` lines.
+    ///
+    /// Office injects `Attribute` statements (such as `Attribute VB_Name = "Module1"`)
+    /// at the start of every module's source code. These aren't displayed by the VBA
+    /// editor. This function returns the source code as displayed in the editor, which
+    /// is useful for comparing it against exported source files. A VB6 file header
+    /// preceding the `Attribute` lines is removed as well.
+    ///
+    /// The removed attributes are available through [`Project::module_attributes`].
+    pub fn module_source_clean(&self, name: &str) -> Result<String> {
+        let source = self.module_source(name)?;
+        let (_, code) = attributes::split_attributes(&source);
+        Ok(code.to_owned())
+    }
+
+    /// Returns a module's source code with NUL characters removed.
+    ///
+    /// VBA source code doesn't ordinarily contain NUL characters. Obfuscated modules
+    /// sometimes embed them to interfere with tools that treat the source code as a C
+    /// string. This function returns the same source code as [`Project::module_source`],
+    /// with all U+0000 characters stripped, along with the number of characters removed.
+    pub fn module_source_no_nul(&self, name: &str) -> Result<(String, usize)> {
+        let mut source = self.module_source(name)?;
+        let len = source.len();
+        source.retain(|c| c != '\0');
+        let removed = len - source.len();
+        Ok((source, removed))
     }
 
     /// Returns the raw source code from a module.
@@ -306,79 +1729,1591 @@ impl Project {
     /// is done. The data is encoded using the project's code page available through
     /// [`Information::code_page`].
     pub fn module_source_raw(&self, name: &str) -> Result<Vec<u8>> {
-        let module = self
-            .modules
-            .iter()
-            .find(|&module| module.name == name)
-            .ok_or_else(|| Error::ModuleNotFound(name.to_owned()))?;
+        self.source_raw_of(self.module(name)?)
+    }
 
-        // `PathBuf::from_str` cannot fail (`type Err = Infallible`). The subsequent
-        // `unwrap` thus won't `panic!`. No path separator normalization is done in the
-        // process; this is intentional.
-        let path = PathBuf::from_str("/VBA").unwrap().join(&module.stream_name);
+    /// Returns the beginning of a module's source code.
+    ///
+    /// This function returns at most the first `max_bytes` bytes of a module's source
+    /// code, converted to a `String` using the project's code page. Only as much of the
+    /// module stream is decompressed as is required to produce the requested number of
+    /// bytes. This makes it suitable for previewing large modules.
+    ///
+    /// The limit applies to the raw source code, prior to character encoding conversion.
+    /// If the limit splits a multi-byte character, the partial character is decoded as
+    /// U+FFFD REPLACEMENT CHARACTER.
+    pub fn module_source_preview(&self, name: &str, max_bytes: usize) -> Result<String> {
+        let module = self.module(name)?;
+        let path = module_stream_path(&self.root, &module.stream_name)?;
+        let data = self
+            .read_stream(path)
+            .map_err(|e| Error::module(&module.name, e))?;
         let offset = module.text_offset;
-        let src_code = self.decompress_stream_from(path, offset)?;
+        let data = data
+            .get(offset..)
+            .ok_or_else(|| Error::module(&module.name, Error::Decompressor { offset }))?;
+        let (_, source_raw) = parser::decompress_prefix(data, max_bytes)
+            .map_err(|e| Error::module(&module.name, parser::decompressor_error(data, e)))?;
+        let source = cp_into_string(source_raw, self.information.code_page)?;
 
-        Ok(src_code)
+        Ok(source)
+    }
+
+    /// Returns the entire contents of a module's stream.
+    ///
+    /// Unlike [`Project::module_source_raw`], the data is neither sliced at the module's
+    /// [`text_offset`](Module::text_offset) nor decompressed. The result holds the
+    /// module's performance cache (p-code), followed by the compressed source code.
+    pub fn raw_module_stream(&self, name: &str) -> Result<Vec<u8>> {
+        let module = self.module(name)?;
+        let path = module_stream_path(&self.root, &module.stream_name)?;
+        self.read_stream(path)
+            .map_err(|e| Error::module(&module.name, e))
     }
 
     /// Returns a stream's contents.
     ///
     /// This is a low-level function operating on the CFB data. The CFB is the storage
-    /// container of the raw binary VBA project.
+    /// container of the raw binary VBA project. Relative paths are resolved against the
+    /// project's [root path](Project::root).
+    ///
+    /// If the project was opened with [`OpenOptions::cache_sources`] enabled, the stream
+    /// is read from the container on first access only.
     pub fn read_stream<P>(&self, stream_path: P) -> Result<Vec<u8>>
     where
         P: AsRef<Path>,
     {
+        // Without caching, the data is owned by the `Arc` alone, and isn't copied.
+        let data = self.stream(stream_path.as_ref())?;
+        Ok(Arc::try_unwrap(data).unwrap_or_else(|data| data.as_ref().clone()))
+    }
+
+    /// Reads a stream's contents into a caller-supplied buffer.
+    ///
+    /// This is the equivalent of [`Project::read_stream`], writing the stream's contents
+    /// to `buffer`. The buffer is cleared first, and its allocation is reused. This allows
+    /// amortizing allocations when reading many streams. On failure, the contents of
+    /// `buffer` are unspecified.
+    pub fn read_stream_into<P>(&self, stream_path: P, buffer: &mut Vec<u8>) -> Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        buffer.clear();
+        if self.options.cache_sources {
+            buffer.extend_from_slice(&self.stream(stream_path.as_ref())?);
+        } else {
+            self.container_mut()
+                .open_stream(self.root.join(stream_path))
+                .map_err(Error::Cfb)?
+                .read_to_end(buffer)
+                .map_err(Error::Cfb)?;
+        }
+        Ok(())
+    }
+
+    /// Returns a stream's contents, served from the stream cache if enabled.
+    ///
+    /// The cache assumes that the container doesn't change behind its back. Functions
+    /// that write to the container (such as [`Project::rename_module`]) clear the cache.
+    fn stream(&self, stream_path: &Path) -> Result<Arc<Vec<u8>>> {
+        let path = self.root.join(stream_path);
+        if self.options.cache_sources {
+            if let Some(data) = self.stream_cache.borrow().get(&path) {
+                return Ok(Arc::clone(data));
+            }
+        }
+
         let mut buffer = Vec::new();
-        self.container
-            .borrow_mut()
-            .open_stream(stream_path)
+        self.container_mut()
+            .open_stream(&path)
             .map_err(Error::Cfb)?
             .read_to_end(&mut buffer)
             .map_err(Error::Cfb)?;
+        let data = Arc::new(buffer);
 
-        Ok(buffer)
+        if self.options.cache_sources {
+            self.stream_cache
+                .borrow_mut()
+                .insert(path, Arc::clone(&data));
+        }
+
+        Ok(data)
+    }
+
+    /// Discards all cached streams and source code.
+    ///
+    /// Caching is enabled through [`OpenOptions::cache_sources`]. Use this function to
+    /// bound the memory held by the caches.
+    pub fn clear_cache(&self) {
+        self.stream_cache.borrow_mut().clear();
+        self.source_cache.borrow_mut().clear();
+    }
+
+    /// Returns the kind of the module called `name`.
+    ///
+    /// The *dir* stream doesn't distinguish document modules, class modules, and designer
+    /// modules (see [`ModuleType::DocClsDesigner`]). For these modules, the kind is
+    /// determined from the line that lists the module in the *PROJECT* stream
+    /// (`Document=`, `Class=`, or `BaseClass=`). Modules that aren't listed are reported
+    /// as [`ModuleKind::Class`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error::ModuleNotFound`] error if there is no module called `name`.
+    /// Reading the *PROJECT* stream can fail with the errors documented for
+    /// [`Project::project_properties`].
+    pub fn module_kind(&self, name: &str) -> Result<ModuleKind> {
+        let module = self.module(name)?;
+        match module.module_type {
+            ModuleType::Procedural => Ok(ModuleKind::Procedural),
+            ModuleType::DocClsDesigner => Ok(self
+                .project_properties()?
+                .module_kind(&module.name)
+                .unwrap_or(ModuleKind::Class)),
+        }
+    }
+
+    /// Returns the properties stored in the project's *PROJECT* stream.
+    ///
+    /// The stream is decoded using the project's code page. Lines that cannot be
+    /// interpreted are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error::Cfb`] error if the stream doesn't exist or cannot be read.
+    pub fn project_properties(&self) -> Result<ProjectProperties> {
+        let data = self.read_stream("PROJECT")?;
+        let text = cp_to_str(&data, self.information.code_page)?;
+
+        Ok(properties::parse_project_properties(&text))
+    }
+
+    /// Returns the project's protection state.
+    ///
+    /// The state is read from the `CMG`, `DPB`, and `GC` properties of the *PROJECT*
+    /// stream, and decrypted as described in \[MS-OVBA\] section 2.4.3. Missing properties
+    /// are reported as unprotected. The password itself isn't recovered.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error::Cfb`] error if the stream doesn't exist or cannot be read, and
+    /// an [`Error::Parser`] error if a property cannot be decrypted. The offset of the
+    /// latter is relative to the start of the property's binary (hex-decoded) value.
+    pub fn protection(&self) -> Result<ProtectionState> {
+        let properties = self.project_properties()?;
+        let decrypt =
+            |value: &Option<String>| value.as_deref().map(properties::decrypt).transpose();
+
+        let mut state = ProtectionState::default();
+        if let Some(data) = decrypt(&properties.protection_state)? {
+            let flags = data.first().copied().unwrap_or_default();
+            state.locked = flags & 0x01 != 0;
+            state.host_protected = flags & 0x02 != 0;
+            state.vbe_protected = flags & 0x04 != 0;
+        }
+        if let Some(data) = decrypt(&properties.password)? {
+            // A single 0x00 byte indicates that there's no password.
+            state.password_protected = data != [0x00];
+        }
+        if let Some(data) = decrypt(&properties.visibility_state)? {
+            // 0x00 indicates that the project isn't visible, 0xFF that it is.
+            state.view_protected = data == [0x00];
+        }
+
+        Ok(state)
+    }
+
+    /// Returns the streams of a designer module's storage.
+    ///
+    /// Designer modules (such as UserForms) store their layout and embedded controls in a
+    /// storage next to the *VBA* storage, named after the module's stream name. Each item
+    /// holds a stream's path relative to the designer storage (e.g. `"f"` or `"i07/f"`,
+    /// using `/` as the separator), along with its raw contents. Streams are returned in
+    /// the order of a preorder traversal.
+    ///
+    /// The contents aren't interpreted.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error::ModuleNotFound`] error if there is no module called
+    /// `module_name`, an [`Error::InvalidStreamName`] error if the module's stream name
+    /// cannot be mapped to a storage path, and an [`Error::Cfb`] error if the module has
+    /// no designer storage, or a stream cannot be read.
+    pub fn designer_storage(&self, module_name: &str) -> Result<Vec<(String, Vec<u8>)>> {
+        let module = self.module(module_name)?;
+        // Applies the same validation as for the module stream.
+        module_stream_path(&self.root, &module.stream_name)?;
+        let storage = self.root.join(&module.stream_name);
+
+        let streams = {
+            let container = self.container();
+            if !container.is_storage(&storage) {
+                return Err(Error::Cfb(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("No designer storage at {:?}", storage),
+                )));
+            }
+            container
+                .walk_storage(&storage)
+                .map_err(Error::Cfb)?
+                .filter(|entry| entry.is_stream())
+                .map(|entry| entry.path().to_path_buf())
+                .collect::<Vec<_>>()
+        };
+
+        streams
+            .into_iter()
+            .map(|path| {
+                let data = self.read_stream(&path)?;
+                let name = path
+                    .strip_prefix(&storage)
+                    .unwrap_or(&path)
+                    .components()
+                    .map(|component| component.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                Ok((name, data))
+            })
+            .collect()
+    }
+
+    /// Returns the project's module names, as stored in the *PROJECTwm* stream.
+    ///
+    /// Each entry holds a module's name in the project's code page, along with its
+    /// Unicode representation. The latter is accurate for names with characters that
+    /// cannot be represented in the project's code page. Entries are returned in stream
+    /// order.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error::Cfb`] error if the stream doesn't exist or cannot be read, and
+    /// an [`Error::Parser`] error if the stream is malformed.
+    pub fn name_map(&self) -> Result<Vec<(String, String)>> {
+        let data = self.read_stream(Path::new("VBA").join("PROJECTwm"))?;
+        let (_, name_map) = parser::parse_name_map(&data, self.information.code_page)
+            .map_err(|e| parser::parser_error(&data, e))?;
+
+        Ok(name_map)
+    }
+
+    /// Returns the VBA version stored in the *_VBA_PROJECT* stream.
+    ///
+    /// The version identifies the VBA implementation that last wrote the project's
+    /// performance cache, and thus the authoring Office build.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error::Cfb`] error if the stream doesn't exist or cannot be read, and
+    /// an [`Error::Parser`] error if the stream doesn't start with the expected signature.
+    pub fn vba_version(&self) -> Result<u16> {
+        let data = self.read_stream(Path::new("VBA").join("_VBA_PROJECT"))?;
+        let (_, version) =
+            parser::parse_vba_project(&data).map_err(|e| parser::parser_error(&data, e))?;
+
+        Ok(version)
+    }
+
+    /// Returns the performance cache stored in the *_VBA_PROJECT* stream.
+    ///
+    /// The performance cache holds compiled (p-code) data in an undocumented,
+    /// version-dependent format. It is returned as is.
+    ///
+    /// # Errors
+    ///
+    /// See [`Project::vba_version`].
+    pub fn performance_cache(&self) -> Result<Vec<u8>> {
+        let data = self.read_stream(Path::new("VBA").join("_VBA_PROJECT"))?;
+        let (performance_cache, _) =
+            parser::parse_vba_project(&data).map_err(|e| parser::parser_error(&data, e))?;
+
+        Ok(performance_cache.to_vec())
+    }
+
+    /// Returns whether the *_VBA_PROJECT* stream holds a performance cache.
+    ///
+    /// # Errors
+    ///
+    /// See [`Project::vba_version`].
+    pub fn has_performance_cache(&self) -> Result<bool> {
+        Ok(!self.performance_cache()?.is_empty())
+    }
+
+    /// Compares the presence of compiled code against the modules' source code.
+    ///
+    /// Office executes a module's compiled code (p-code), if present and compatible with
+    /// the running VBA version, rather than its source code. A technique known as *VBA
+    /// stomping* exploits this by replacing the source code, so that the displayed source
+    /// code doesn't reflect what executes. Compiled code is stored in the *_VBA_PROJECT*
+    /// stream's performance cache, and in each module stream ahead of the source code
+    /// (see [`Module::text_offset`]).
+    ///
+    /// This is a heuristic. The compiled code isn't decoded, and the VBA version that
+    /// produced it isn't compared against any particular Office version.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the *_VBA_PROJECT* stream or a module's source code cannot be
+    /// read.
+    pub fn source_cache_consistency(&self) -> Result<CacheStatus> {
+        let cached_modules = self
+            .modules
+            .iter()
+            .filter(|module| module.text_offset > 0)
+            .collect::<Vec<_>>();
+        if cached_modules.is_empty() && !self.has_performance_cache()? {
+            return Ok(CacheStatus::SourceOnly);
+        }
+
+        let mut missing = Vec::new();
+        for module in cached_modules {
+            let source = self.source_of(module)?;
+            let (_, code) = attributes::split_attributes(&source);
+            if code.trim().is_empty() {
+                missing.push(module.name.clone());
+            }
+        }
+        if missing.is_empty() {
+            Ok(CacheStatus::CachePresent)
+        } else {
+            Ok(CacheStatus::SourceMissing(missing))
+        }
+    }
+
+    /// Returns the raw digital signature of the VBA project, if the project is signed.
+    ///
+    /// Signatures are stored in streams next to the *VBA* storage, below the project's
+    /// [root path](Project::root). Office writes up to three signatures, using different
+    /// hashing schemes: *\u{5}DigitalSignatureExt* (V3), *\u{5}DigitalSignatureEx*
+    /// (agile), and the legacy *\u{5}DigitalSignature*. The contents of the first stream
+    /// present, in this order, are returned.
+    ///
+    /// The signature is returned as stored, i.e. the PKCS #7 data wrapped in a
+    /// `DigSigInfoSerialized` structure (\[MS-OSHARED\] section 2.3.2). It isn't parsed or
+    /// verified.
+    pub fn signature(&self) -> Result<Option<Vec<u8>>> {
+        let stream_name = SIGNATURE_STREAMS
+            .iter()
+            .find(|stream_name| self.container().is_stream(self.root.join(stream_name)));
+        stream_name
+            .map(|stream_name| self.read_stream(stream_name))
+            .transpose()
+    }
+
+    /// Returns whether the VBA project is digitally signed.
+    ///
+    /// See [`Project::signature`] for details. The signature isn't verified.
+    pub fn is_signed(&self) -> bool {
+        SIGNATURE_STREAMS
+            .iter()
+            .any(|stream_name| self.container().is_stream(self.root.join(stream_name)))
+    }
+
+    /// Writes the project's CFB container to `writer`.
+    ///
+    /// This copies every storage and stream of the container, including storages and
+    /// streams unrelated to the VBA project. Storage CLSIDs and state bits are preserved.
+    /// The CFB version matches that of the original container.
+    ///
+    /// If `information`, `references`, and `modules` are unchanged, the container's bytes
+    /// are copied to `writer` verbatim. Changes made through [`Project::rename_module`]
+    /// are part of the container, and don't count as changes here.
+    ///
+    /// Otherwise (e.g. after [`Project::set_module_flags`]), the *dir* stream is rebuilt
+    /// from them using a [`DirBuilder`], and all other streams are written as they are
+    /// stored in the container. Unknown records, and values that the specification
+    /// requires readers to ignore, aren't preserved in that case. The CFB data is
+    /// assembled in memory, and passed to `writer` in a single call to `write_all`. The
+    /// sector layout and entry timestamps of the result can differ from the original
+    /// container.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error::Unsupported`] error if the *dir* stream needs to be rebuilt,
+    /// but the project was opened with a replacement code page (see
+    /// [`Project::rename_module`]), and an [`Error::Encoding`] error if a string cannot
+    /// be represented in the project's code page.
+    ///
+    /// # Panics
+    ///
+    /// Copying the container's bytes requires reopening the container. If that fails,
+    /// the error is returned, and any further use of the project panics.
+    pub fn write_to<W: Write + Seek>(&self, mut writer: W) -> Result<()> {
+        let metadata = self.clone_metadata();
+        if metadata == self.stored_metadata {
+            return self.copy_container(&mut writer);
+        }
+        self.ensure_declared_code_page()?;
+        let dir = compress(&DirBuilder::from(metadata).build()?);
+        let dir_path = self.root.join("VBA").join("dir");
+
+        let mut container = self.container_mut();
+        let entries = container
+            .walk()
+            .map(|entry| {
+                let path = entry.path().to_owned();
+                (path, entry.is_stream(), *entry.clsid(), entry.state_bits())
+            })
+            .collect::<Vec<_>>();
+
+        let mut target =
+            CompoundFile::create_with_version(container.version(), Cursor::new(Vec::new()))
+                .map_err(Error::Cfb)?;
+        for (path, is_stream, clsid, state_bits) in entries {
+            if is_stream {
+                let mut stream = target.create_stream(&path).map_err(Error::Cfb)?;
+                if is_same_path(&path, &dir_path) {
+                    stream.write_all(&dir).map_err(Error::Cfb)?;
+                } else {
+                    let mut source = container.open_stream(&path).map_err(Error::Cfb)?;
+                    io::copy(&mut source, &mut stream).map_err(Error::Cfb)?;
+                }
+            } else {
+                if path != Path::new("/") {
+                    target.create_storage(&path).map_err(Error::Cfb)?;
+                }
+                target.set_storage_clsid(&path, clsid).map_err(Error::Cfb)?;
+            }
+            target
+                .set_state_bits(&path, state_bits)
+                .map_err(Error::Cfb)?;
+        }
+        target.flush().map_err(Error::Cfb)?;
+
+        writer
+            .write_all(target.into_inner().get_ref())
+            .map_err(Error::Io)
+    }
+
+    /// Borrows the container.
+    fn container(&self) -> Ref<'_, CompoundFile<R>> {
+        Ref::map(self.container.borrow(), |container| {
+            container.as_ref().expect(CONTAINER_TAKEN)
+        })
+    }
+
+    /// Mutably borrows the container.
+    fn container_mut(&self) -> RefMut<'_, CompoundFile<R>> {
+        RefMut::map(self.container.borrow_mut(), |container| {
+            container.as_mut().expect(CONTAINER_TAKEN)
+        })
+    }
+
+    /// Copies the container's bytes to `writer`, and reopens the container.
+    fn copy_container<W: Write>(&self, writer: &mut W) -> Result<()> {
+        let mut slot = self.container.borrow_mut();
+        let mut inner = slot.take().expect(CONTAINER_TAKEN).into_inner();
+        let copied = inner
+            .seek(SeekFrom::Start(0))
+            .and_then(|_| io::copy(&mut inner, writer));
+        *slot = Some(CompoundFile::open(inner).map_err(Error::Cfb)?);
+        copied.map(|_| ()).map_err(Error::Io)
+    }
+
+    /// Returns the source code of `module`. See [`Project::module_source`].
+    fn source_of(&self, module: &Module) -> Result<String> {
+        if self.options.cache_sources {
+            if let Some(source) = self.source_cache.borrow().get(&module.name) {
+                return Ok(source.clone());
+            }
+        }
+
+        let source_raw = self.source_raw_of(module)?;
+        let source = cp_into_string(source_raw, self.information.code_page)?;
+
+        if self.options.cache_sources {
+            self.source_cache
+                .borrow_mut()
+                .insert(module.name.clone(), source.clone());
+        }
+
+        Ok(source)
+    }
+
+    /// Returns the raw source code of `module`. See [`Project::module_source_raw`].
+    fn source_raw_of(&self, module: &Module) -> Result<Vec<u8>> {
+        let path = module_stream_path(&self.root, &module.stream_name)?;
+        let offset = module.text_offset;
+        let src_code = self
+            .decompress_stream_from(path, offset)
+            .map_err(|e| Error::module(&module.name, e))?;
+
+        Ok(src_code)
+    }
+
+    /// Returns the module called `name`, or an [`Error::ModuleNotFound`] error.
+    fn module(&self, name: &str) -> Result<&Module> {
+        self.find_module(name)
+            .ok_or_else(|| Error::ModuleNotFound(name.to_owned()))
+    }
+
+    /// Sets the `read_only` and `private` flags of the module called `name`.
+    ///
+    /// This only updates the in-memory [`Module`]. The change is written to the *dir*
+    /// stream by [`Project::write_to`]. [`Project::reload`] restores the flags stored in
+    /// the container.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error::ModuleNotFound`] error if there is no module called `name`.
+    pub fn set_module_flags(&mut self, name: &str, read_only: bool, private: bool) -> Result<()> {
+        let module = self
+            .modules
+            .iter_mut()
+            .find(|module| module.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| Error::ModuleNotFound(name.to_owned()))?;
+        module.read_only = read_only;
+        module.private = private;
+        Ok(())
+    }
+
+    /// Lends the underlying CFB container to `f`, and returns its result.
+    ///
+    /// This is an escape hatch for use cases not covered by this crate's API. It allows
+    /// client code to run arbitrary read-only operations using the [`cfb`] crate API
+    /// directly, such as enumerating entries or inspecting metadata.
+    ///
+    /// The container is borrowed for the duration of the call. Calling back into the
+    /// `Project` from inside `f` to read streams will `panic!`.
+    pub fn with_container<F, T>(&self, f: F) -> T
+    where
+        F: FnOnce(&CompoundFile<R>) -> T,
+    {
+        f(&self.container())
+    }
+
+    /// Returns a copy of the project's metadata that is independent of the CFB
+    /// container.
+    ///
+    /// This allows dropping the `Project`, and with it the underlying reader, while
+    /// retaining `information`, `references`, and `modules`.
+    pub fn clone_metadata(&self) -> ProjectMetadata {
+        ProjectMetadata {
+            information: self.information.clone(),
+            references: self.references.clone(),
+            modules: self.modules.clone(),
+        }
+    }
+
+    /// Decomposes the project into its metadata and the CFB container.
+    ///
+    /// The metadata can be shared between threads freely, while the container is moved
+    /// to the thread that reads streams. The project's root path, as returned by
+    /// [`Project::root`], locates the *VBA* storage within the container.
+    pub fn into_parts(self) -> (ProjectMetadata, CompoundFile<R>) {
+        let metadata = ProjectMetadata {
+            information: self.information,
+            references: self.references,
+            modules: self.modules,
+        };
+        (
+            metadata,
+            self.container.into_inner().expect(CONTAINER_TAKEN),
+        )
+    }
+
+    /// Compares the project against `other`.
+    ///
+    /// Modules are compared by their decoded source code, so that source code stored
+    /// using different code pages compares equal if it decodes to the same text. The
+    /// modules' [`Module::text_offset`] and [`Module::cookie`] are ignored, as they change
+    /// whenever the compiled code changes. References are compared as a set.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the source code of a module present in both projects cannot be
+    /// read.
+    pub fn diff<R2: Read + Seek>(&self, other: &Project<R2>) -> Result<ProjectDiff> {
+        let mut diff = ProjectDiff {
+            information_changed: self.information != other.information,
+            ..ProjectDiff::default()
+        };
+
+        for module in &self.modules {
+            let other_module = match other.find_module(&module.name) {
+                Some(other_module) => other_module,
+                None => {
+                    diff.removed_modules.push(module.name.clone());
+                    continue;
+                }
+            };
+            let metadata = |module: &Module| Module {
+                text_offset: 0,
+                cookie: 0,
+                ..module.clone()
+            };
+            let metadata_changed = metadata(module) != metadata(other_module);
+            let source = self.source_of(module)?;
+            let other_source = other.source_of(other_module)?;
+            let source = if source != other_source {
+                Some((source, other_source))
+            } else {
+                None
+            };
+            if metadata_changed || source.is_some() {
+                diff.modified_modules.push(ModuleDiff {
+                    name: module.name.clone(),
+                    metadata_changed,
+                    source,
+                });
+            }
+        }
+        diff.added_modules = other
+            .modules
+            .iter()
+            .filter(|module| self.find_module(&module.name).is_none())
+            .map(|module| module.name.clone())
+            .collect();
+
+        diff.added_references = other
+            .references
+            .iter()
+            .filter(|reference| !self.references.contains(reference))
+            .cloned()
+            .collect();
+        diff.removed_references = self
+            .references
+            .iter()
+            .filter(|reference| !other.references.contains(reference))
+            .cloned()
+            .collect();
+
+        Ok(diff)
+    }
+
+    /// Returns an overview of the project.
+    ///
+    /// The summary collects the project's platform, code page, VBA version, module
+    /// counts, and reference libids. Its `Display` implementation renders a readable
+    /// block of text, suitable for diagnostic output.
+    pub fn summary(&self) -> ProjectSummary {
+        let procedural_modules = self
+            .modules
+            .iter()
+            .filter(|module| matches!(module.module_type, ModuleType::Procedural))
+            .count();
+
+        ProjectSummary {
+            name: self.information.name.clone(),
+            sys_kind: self.information.sys_kind.clone(),
+            code_page: self.information.code_page,
+            vba_version: self.vba_version().ok(),
+            procedural_modules,
+            doc_cls_designer_modules: self.modules.len() - procedural_modules,
+            references: self.reference_libids().map(str::to_owned).collect(),
+        }
+    }
+
+    /// Returns the path of the storage that contains the project's *VBA* storage.
+    ///
+    /// This is `/` unless the project was opened with [`open_project_with_path`].
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Returns the diagnostics recorded while opening the project.
+    ///
+    /// The list is empty unless the project was opened with relaxed validation rules
+    /// through [`OpenOptions`].
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    /// Returns the number of malformed references that were skipped.
+    ///
+    /// References are only skipped when the project is opened in lenient mode (see
+    /// [`OpenOptions::lenient`]). Each skipped reference is also reported as a
+    /// [`Warning::SkippedReference`].
+    pub fn skipped_reference_count(&self) -> usize {
+        self.warnings
+            .iter()
+            .filter(|warning| matches!(warning, Warning::SkippedReference { .. }))
+            .count()
+    }
+
+    /// Returns the decompressed *dir* stream.
+    ///
+    /// The stream is read from the underlying container and decompressed, but not parsed.
+    /// Offsets reported by [`Error::Parser`] errors and [`Warning`]s refer to this data,
+    /// and [`iter_dir_records`] splits it into records. This is mostly useful for
+    /// diagnostics. Use [`raw_dir_from_reader`] if the project cannot be opened.
+    pub fn raw_dir(&self) -> Result<Vec<u8>> {
+        read_dir_stream(&mut self.container_mut(), &self.root, &self.options)
+    }
+
+    /// Fails with an [`Error::Unsupported`] error if `information.code_page` may differ
+    /// from the code page declared in the *dir* stream, i.e. if a code page override or
+    /// fallback encoding was in effect when opening the project.
+    ///
+    /// Writing the *dir* stream, or any data derived from it, would otherwise persist the
+    /// replacement code page.
+    fn ensure_declared_code_page(&self) -> Result<()> {
+        let is_fallback = self
+            .warnings
+            .iter()
+            .any(|warning| matches!(warning, Warning::EncodingFallback { .. }));
+        if self.options.code_page.is_some() || is_fallback {
+            return Err(Error::Unsupported(
+                "writing a project opened with a replacement code page",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Re-reads and re-parses the *dir* stream from the underlying container.
+    ///
+    /// The [`OpenOptions`] used to open the project apply. On success, `information`,
+    /// `references`, `modules`, and [`warnings()`](Project::warnings) are replaced with
+    /// the freshly parsed values, and cached streams and source code are discarded. On
+    /// failure, the project is left unchanged.
+    pub fn reload(&mut self) -> Result<()> {
+        let (information, warnings) = read_project_information(
+            self.container.get_mut().as_mut().expect(CONTAINER_TAKEN),
+            &self.root,
+            &self.options,
+            None,
+        )?;
+
+        self.information = information.information.clone();
+        self.references = information.references.clone();
+        self.modules = information.modules.clone();
+        self.warnings = warnings;
+        self.stored_metadata = information;
+        self.source_cache.get_mut().clear();
+        self.stream_cache.get_mut().clear();
+
+        Ok(())
     }
 }
 
-/// Opens a VBA project.
+#[cfg(feature = "std")]
+impl<R: Read + Write + Seek> Project<R> {
+    /// Renames the module called `old` to `new`, and writes the change to the underlying
+    /// container.
+    ///
+    /// The following updates are made, so that the project remains consistent:
+    ///
+    /// * The module's stream is renamed to `new`. Its source code is stored without a
+    ///   performance cache, and its `Attribute VB_Name` line is updated.
+    /// * The *dir* stream is rebuilt from the project's metadata using a [`DirBuilder`],
+    ///   with the module's name and stream name set to `new`.
+    /// * The module's lines in the *PROJECT* stream, and its entry in the *PROJECTwm*
+    ///   stream, are updated, if the respective stream exists.
+    /// * The *_VBA_PROJECT* stream is replaced with a stream that holds no performance
+    ///   cache, as mandated by \[MS-OVBA\] section 2.3.4.1. This invalidates the
+    ///   performance caches of all modules, and makes the host recompile the project from
+    ///   source.
+    ///
+    /// Any pending changes to `information`, `references`, and `modules` are written to
+    /// the *dir* stream as well. Unknown records, and values that the specification
+    /// requires readers to ignore, aren't preserved. Use [`Project::write_to`] to obtain
+    /// the updated container.
+    ///
+    /// Module names are compared case-insensitively. `new` isn't validated as a VBA
+    /// identifier.
+    ///
+    /// Designer modules (such as `UserForm`s) aren't supported, as their designer storage
+    /// would keep the old name. Neither are projects opened with a code page override
+    /// ([`OpenOptions::code_page`]) or a fallback encoding in effect
+    /// ([`OpenOptions::fallback_encoding`]), as the *dir* stream would be written with the
+    /// replacement code page.
+    ///
+    /// # Errors
+    ///
+    /// * Returns an [`Error::ModuleNotFound`] error if there is no module called `old`.
+    /// * Returns an [`Error::DuplicateModule`] error if another module is called `new`, or
+    ///   another entry of the *VBA* storage (including other modules' streams) has that
+    ///   name.
+    /// * Returns an [`Error::InvalidStreamName`] error if `new` cannot be used as a
+    ///   stream name.
+    /// * Returns an [`Error::Unsupported`] error for a designer module, or if the
+    ///   project's code page was replaced.
+    /// * Returns an [`Error::Encoding`] error if `new`, or the updated contents of a
+    ///   stream, cannot be represented in the project's code page.
+    ///
+    /// These errors, and any error reading the affected streams, are reported before the
+    /// container is modified. An [`Error::Cfb`] error while writing the streams can leave
+    /// the container partially updated.
+    pub fn rename_module(&mut self, old: &str, new: &str) -> Result<()> {
+        let index = self
+            .modules
+            .iter()
+            .position(|module| module.name.eq_ignore_ascii_case(old))
+            .ok_or_else(|| Error::ModuleNotFound(old.to_owned()))?;
+        // `new` becomes the module's stream name as well. It mustn't refer to any other
+        // module's stream, or any other entry of the *VBA* storage (such as the *dir*
+        // stream), as those would be overwritten. The streams defined by the
+        // specification are reserved, whether or not they exist.
+        const RESERVED: &[&str] = &["dir", "_VBA_PROJECT", "PROJECTwm", "PROJECTlk"];
+        let old_stream_name = &self.modules[index].stream_name;
+        let is_reserved = RESERVED.iter().any(|name| name.eq_ignore_ascii_case(new))
+            || new
+                .get(..6)
+                .is_some_and(|prefix| prefix.eq_ignore_ascii_case("__SRP_"));
+        let is_duplicate = is_reserved
+            || self.modules.iter().enumerate().any(|(i, module)| {
+                i != index
+                    && (module.name.eq_ignore_ascii_case(new)
+                        || module.stream_name.eq_ignore_ascii_case(new))
+            })
+            || self
+                .container()
+                .read_storage(self.root.join("VBA"))
+                .map_err(Error::Cfb)?
+                .any(|entry| {
+                    entry.name().eq_ignore_ascii_case(new)
+                        && !entry.name().eq_ignore_ascii_case(old_stream_name)
+                });
+        if is_duplicate {
+            return Err(Error::DuplicateModule(new.to_owned()));
+        }
+        self.ensure_declared_code_page()?;
+        let module = &self.modules[index];
+        let old_path = module_stream_path(&self.root, &module.stream_name)?;
+        // The designer storage is named after the stream name. See
+        // `Project::designer_storage`.
+        if self
+            .container()
+            .is_storage(self.root.join(&module.stream_name))
+        {
+            return Err(Error::Unsupported("renaming designer modules"));
+        }
+
+        // Prepare all streams before modifying the container.
+        let code_page = self.information.code_page;
+        let old_name = module.name.clone();
+        let new_path = module_stream_path(&self.root, new)?;
+
+        let source = self.source_of(module)?;
+        let source = attributes::replace_vb_name(&source, new).unwrap_or(source);
+        let source = compress(&writer::encode_cp(&source, code_page)?);
+
+        let mut streams = Vec::new();
+        let project_path = PathBuf::from("PROJECT");
+        if self.container().is_stream(self.root.join(&project_path)) {
+            let text = parser::cp_to_string(&self.read_stream(&project_path)?, code_page)?;
+            let text = properties::rename_module(&text, &old_name, new);
+            streams.push((project_path, writer::encode_cp(&text, code_page)?));
+        }
+        let name_map_path = Path::new("VBA").join("PROJECTwm");
+        if self.container().is_stream(self.root.join(&name_map_path)) {
+            let data = self.read_stream(&name_map_path)?;
+            let (remainder, mut name_map) = parser::parse_name_map(&data, code_page)
+                .map_err(|e| parser::parser_error(&data, e))?;
+            for (name, name_unicode) in &mut name_map {
+                if name_unicode.eq_ignore_ascii_case(&old_name) {
+                    *name = new.to_owned();
+                    *name_unicode = new.to_owned();
+                }
+            }
+            let mut name_map = writer::write_name_map(&name_map, code_page)?;
+            name_map.extend_from_slice(remainder);
+            streams.push((name_map_path, name_map));
+        }
+
+        let mut metadata = self.clone_metadata();
+        let module = &mut metadata.modules[index];
+        module.name = new.to_owned();
+        module.stream_name = new.to_owned();
+        module.text_offset = 0;
+        let dir = DirBuilder::from(metadata.clone()).build()?;
+        streams.push((Path::new("VBA").join("dir"), compress(&dir)));
+
+        // Reserved1, Version (0xFFFF on write), Reserved2, Reserved3; no PerformanceCache
+        const VBA_PROJECT: &[u8] = &[0xcc, 0x61, 0xff, 0xff, 0x00, 0x00, 0x00];
+        streams.push((Path::new("VBA").join("_VBA_PROJECT"), VBA_PROJECT.to_vec()));
+
+        // The old stream is removed first; CFB entry names are compared
+        // case-insensitively.
+        let container = self.container.get_mut().as_mut().expect(CONTAINER_TAKEN);
+        container.remove_stream(&old_path).map_err(Error::Cfb)?;
+        container
+            .create_stream(&new_path)
+            .map_err(Error::Cfb)?
+            .write_all(&source)
+            .map_err(Error::Cfb)?;
+        for (path, data) in streams {
+            container
+                .create_stream(self.root.join(path))
+                .map_err(Error::Cfb)?
+                .write_all(&data)
+                .map_err(Error::Cfb)?;
+        }
+        container.flush().map_err(Error::Cfb)?;
+
+        self.modules = metadata.modules.clone();
+        self.stored_metadata = metadata;
+        self.source_cache.get_mut().clear();
+        self.stream_cache.get_mut().clear();
+
+        Ok(())
+    }
+}
+
+impl OpenOptions {
+    /// The default limit for the size of decompressed streams, 64 MiB.
+    pub const DEFAULT_MAX_DECOMPRESSED_SIZE: usize = 64 * 1024 * 1024;
+
+    /// Creates a blank new set of options.
+    ///
+    /// All flags are initially set to `false`, and the size of decompressed streams is
+    /// limited to [`OpenOptions::DEFAULT_MAX_DECOMPRESSED_SIZE`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the option for forward-compatibility mode.
+    ///
+    /// When enabled, records in the *dir* stream with an unknown ID are skipped, as
+    /// long as they follow the common `Id`, `Size`, `Data` layout. This allows opening
+    /// projects produced by future versions of Office that introduce new, optional
+    /// records. Each skipped record is reported as a [`Warning::UnknownRecord`].
+    pub fn forward_compat(&mut self, forward_compat: bool) -> &mut Self {
+        self.forward_compat = forward_compat;
+        self
+    }
+
+    /// Sets the option for lenient mode.
+    ///
+    /// When enabled, deviations from the file format specification are tolerated, as long
+    /// as the project can still be parsed unambiguously. In particular, a missing
+    /// terminator at the end of the *dir* stream is accepted, trailing data is ignored,
+    /// and malformed references are skipped. Each tolerated deviation is reported through
+    /// [`Project::warnings`].
+    ///
+    /// In strict mode (the default), these deviations cause opening the project to fail.
+    pub fn lenient(&mut self, lenient: bool) -> &mut Self {
+        self.lenient = lenient;
+        self
+    }
+
+    /// Sets the option for caching module source code.
+    ///
+    /// When enabled, [`Project::module_source`] keeps the decoded source code of every
+    /// module it returns, and serves subsequent requests for the same module from memory.
+    /// Likewise, streams read from the CFB container (e.g. through
+    /// [`Project::read_stream`]) are kept in memory. This benefits interactive use, where
+    /// modules are accessed repeatedly, at the expense of holding all accessed data in
+    /// memory until [`Project::clear_cache`] is called.
+    ///
+    /// Caching is disabled by default, which suits one-shot extraction.
+    pub fn cache_sources(&mut self, cache_sources: bool) -> &mut Self {
+        self.cache_sources = cache_sources;
+        self
+    }
+
+    /// Sets the option for falling back to a default encoding.
+    ///
+    /// When enabled, a project whose code page cannot be mapped to an encoding is decoded
+    /// using Windows-1252 (code page 1252) instead. This affects all strings in the *dir*
+    /// stream, and the source code of every module. The substitution is reported as a
+    /// [`Warning::EncodingFallback`].
+    ///
+    /// When disabled (the default), opening such a project fails with an
+    /// [`Error::Encoding`] error.
+    pub fn fallback_encoding(&mut self, fallback_encoding: bool) -> &mut Self {
+        self.fallback_encoding = fallback_encoding;
+        self
+    }
+
+    /// Overrides the project's code page.
+    ///
+    /// When set, all MBCS strings in the *dir* stream, and the source code of every
+    /// module, are decoded using `code_page` instead of the code page declared by the
+    /// project. This allows reading projects that declare a wrong code page, e.g. CJK
+    /// source code labeled as Windows-1252. [`Information::code_page`] reports the
+    /// override. Pass `None` (the default) to use the declared code page.
+    ///
+    /// Opening a project fails with an [`Error::Encoding`] error if `code_page` cannot be
+    /// mapped to an encoding, irrespective of [`OpenOptions::fallback_encoding`].
+    pub fn code_page(&mut self, code_page: Option<u16>) -> &mut Self {
+        self.code_page = code_page;
+        self
+    }
+
+    /// Sets the option for verifying duplicate records.
+    ///
+    /// The *dir* stream stores several strings twice: MBCS strings are followed by a
+    /// Unicode record holding their UTF-16 encoding, and the help file path is stored in
+    /// two records. The specification requires the copies to match, so that they are
+    /// ignored by default. When enabled, each copy that doesn't match is reported as a
+    /// [`Warning::MismatchedDuplicate`], as mismatches can be a sign of tampering or a
+    /// faulty producer.
+    ///
+    /// A module's doc string that is only stored in its Unicode record isn't reported,
+    /// as Office stores doc strings that cannot be represented in the project's code page
+    /// this way.
+    pub fn verify_duplicates(&mut self, verify_duplicates: bool) -> &mut Self {
+        self.verify_duplicates = verify_duplicates;
+        self
+    }
+
+    /// Sets the maximum size of decompressed streams, in bytes.
+    ///
+    /// Compressed data can decompress to a multiple of its size. This limit protects
+    /// against malicious input that would otherwise exhaust memory. It applies to the *dir*
+    /// stream, module source code, and [`Project::decompress_stream_from`]. Decompressing
+    /// a stream that exceeds the limit fails with an [`Error::Decompressor`] error.
+    ///
+    /// The limit defaults to [`OpenOptions::DEFAULT_MAX_DECOMPRESSED_SIZE`]. Pass
+    /// `usize::MAX` to disable it.
+    pub fn max_decompressed_size(&mut self, max_decompressed_size: usize) -> &mut Self {
+        self.max_decompressed_size = max_decompressed_size;
+        self
+    }
+
+    /// Parses a decompressed *dir* stream with the options specified by `self`.
+    ///
+    /// This is the equivalent of [`parse_dir`] that honors `self`, and additionally
+    /// returns the [`Warning`]s recorded while parsing. The `cache_sources` and
+    /// `max_decompressed_size` options have no effect.
+    pub fn parse_dir(&self, decompressed: &[u8]) -> Result<(ProjectMetadata, Vec<Warning>)> {
+        self.parse_dir_observed(decompressed, None)
+    }
+
+    /// Same as [`OpenOptions::parse_dir`], passing progress events to `observer`.
+    fn parse_dir_observed(
+        &self,
+        decompressed: &[u8],
+        observer: Option<parser::Observer<'_>>,
+    ) -> Result<(ProjectMetadata, Vec<Warning>)> {
+        let mut state = parser::ParseState::new(self, decompressed);
+        state.observer = observer;
+        let (remainder, metadata) =
+            parser::parse_project_information_with(decompressed, &mut state)
+                .map_err(|e| parser::dir_parser_error(decompressed, e))?;
+        parser::ensure_consumed(decompressed, remainder)?;
+
+        Ok((metadata, state.warnings))
+    }
+}
+
+#[cfg(feature = "std")]
+impl OpenOptions {
+    /// Opens a VBA project with the options specified by `self`.
+    ///
+    /// This function consumes `raw` and returns a [`Project`] struct on success,
+    /// populated with data from the parsed binary input.
+    pub fn open(&self, raw: Vec<u8>) -> Result<Project> {
+        self.open_from(Cursor::new(raw))
+    }
+
+    /// Opens a VBA project with the options specified by `self`, reporting progress to
+    /// `observer`.
+    ///
+    /// This is the equivalent of [`OpenOptions::open`] that passes a [`ParseEvent`] to
+    /// `observer` as each stage of parsing completes. When processing large batches of
+    /// documents, the last event received before an error (or a hang) tells how far
+    /// parsing got. The observer cannot affect the result.
+    pub fn open_with_observer(
+        &self,
+        raw: Vec<u8>,
+        observer: &mut dyn FnMut(ParseEvent<'_>),
+    ) -> Result<Project> {
+        let container = CompoundFile::open(Cursor::new(raw)).map_err(Error::Cfb)?;
+        let root = default_root(&container);
+        self.open_observed(container, &root, Some(observer))
+    }
+
+    /// Opens a VBA project from `reader` with the options specified by `self`.
+    ///
+    /// Unlike [`OpenOptions::open`], this doesn't require the entire CFB container to be
+    /// held in memory. Streams are read from `reader` as needed.
+    ///
+    /// If the container doesn't hold a */VBA/dir* stream, the VBA project is located
+    /// using [`locate_vba_project`]. This allows opening binary Office documents without
+    /// knowledge of host-specific storage paths. Use [`OpenOptions::open_with_path`] to
+    /// open a specific project.
+    pub fn open_from<R: Read + Seek>(&self, reader: R) -> Result<Project<R>> {
+        let container = CompoundFile::open(reader).map_err(Error::Cfb)?;
+        let root = default_root(&container);
+        self.open_with_container(container, root)
+    }
+
+    /// Opens the VBA project stored below `root` in the CFB container read from `reader`,
+    /// with the options specified by `self`.
+    ///
+    /// `root` is the path of the storage that contains the project's *VBA* storage. This
+    /// is `/` for a standalone *vbaProject.bin*, but differs for VBA projects embedded
+    /// in binary Office documents, e.g. `/Macros` for Word documents or
+    /// `/_VBA_PROJECT_CUR` for Excel workbooks. [`Project::discover_projects`] returns
+    /// the root paths of all VBA projects in a container.
+    pub fn open_with_path<P, R>(&self, root: P, reader: R) -> Result<Project<R>>
+    where
+        P: AsRef<Path>,
+        R: Read + Seek,
+    {
+        let container = CompoundFile::open(reader).map_err(Error::Cfb)?;
+        self.open_with_container(container, root)
+    }
+
+    /// Opens the VBA project stored below `root` in an already opened CFB `container`,
+    /// with the options specified by `self`.
+    ///
+    /// This allows client code that inspects other parts of a container through the
+    /// [`cfb`] crate to open a VBA project without parsing the container twice. See
+    /// [`OpenOptions::open_with_path`] for the meaning of `root`.
+    pub fn open_with_container<P, R>(
+        &self,
+        container: CompoundFile<R>,
+        root: P,
+    ) -> Result<Project<R>>
+    where
+        P: AsRef<Path>,
+        R: Read + Seek,
+    {
+        self.open_observed(container, root.as_ref(), None)
+    }
+
+    /// Opens the VBA project stored below `root` in `container`, passing progress events
+    /// to `observer`.
+    fn open_observed<R: Read + Seek>(
+        &self,
+        mut container: CompoundFile<R>,
+        root: &Path,
+        observer: Option<parser::Observer<'_>>,
+    ) -> Result<Project<R>> {
+        let root = Path::new("/").join(root);
+
+        let (information, warnings) =
+            read_project_information(&mut container, &root, self, observer)?;
+
+        Ok(Project {
+            information: information.information.clone(),
+            references: information.references.clone(),
+            modules: information.modules.clone(),
+            container: RefCell::new(Some(container)),
+            root,
+            options: self.clone(),
+            warnings,
+            stored_metadata: information,
+            source_cache: RefCell::new(HashMap::new()),
+            stream_cache: RefCell::new(HashMap::new()),
+        })
+    }
+}
+
+/// Returns the root path of the VBA project in `container`.
 ///
-/// This function consumes `raw` and returns a [`Project`] struct on success, populated
-/// with data from the parsed binary input.
-pub fn open_project(raw: Vec<u8>) -> Result<Project> {
-    let cursor = Cursor::new(raw);
-    let mut container = CompoundFile::open(cursor).map_err(Error::Cfb)?;
+/// This is `/` if the container holds a */VBA/dir* stream, or the parent of the *VBA*
+/// storage found by [`locate_vba_project`] otherwise. If neither exists, `/` is returned,
+/// so that subsequent lookups fail with a meaningful error.
+#[cfg(feature = "std")]
+fn default_root<R: Read + Seek>(container: &CompoundFile<R>) -> PathBuf {
+    let root = if container.is_stream("/VBA/dir") {
+        None
+    } else {
+        locate_vba_project(container)
+            .and_then(|vba_storage| vba_storage.parent().map(Path::to_path_buf))
+    };
+    root.unwrap_or_else(|| PathBuf::from("/"))
+}
 
-    // Read *dir* stream
-    #[cfg(target_family = "windows")]
-    const DIR_STREAM_PATH: &str = "/VBA\\dir";
-    #[cfg(not(target_family = "windows"))]
-    const DIR_STREAM_PATH: &str = "/VBA/dir";
+/// Reads, decompresses, and parses the *dir* stream of the project stored below `root`
+/// in `container`.
+#[cfg(feature = "std")]
+fn read_project_information<F: Read + Seek>(
+    container: &mut CompoundFile<F>,
+    root: &Path,
+    options: &OpenOptions,
+    mut observer: Option<parser::Observer<'_>>,
+) -> Result<(ProjectMetadata, Vec<Warning>)> {
+    let buffer = read_dir_stream(container, root, options)?;
+    if let Some(observer) = &mut observer {
+        observer(ParseEvent::DirDecompressed { len: buffer.len() });
+    }
+    options.parse_dir_observed(&buffer, observer)
+}
 
+/// Reads and decompresses the *dir* stream of the project stored below `root` in
+/// `container`.
+#[cfg(feature = "std")]
+fn read_dir_stream<F: Read + Seek>(
+    container: &mut CompoundFile<F>,
+    root: &Path,
+    options: &OpenOptions,
+) -> Result<Vec<u8>> {
+    // Read *dir* stream
     let mut buffer = Vec::new();
     container
-        .open_stream(DIR_STREAM_PATH)
+        .open_stream(root.join("VBA").join("dir"))
         .map_err(Error::Cfb)?
         .read_to_end(&mut buffer)
         .map_err(Error::Cfb)?;
 
     // Decompress stream
-    let (remainder, buffer) = parser::decompress(&buffer).map_err(|_| Error::Decompressor)?;
-    debug_assert!(remainder.is_empty());
-
-    // Parse binary data
-    let (remainder, information) =
-        parser::parse_project_information(&buffer).map_err(|_| Error::Parser)?;
-    debug_assert_eq!(remainder.len(), 0, "Stream not fully consumed");
-
-    Ok(Project {
-        information: information.information,
-        references: information.references,
-        modules: information.modules,
-        container: RefCell::new(container),
+    let (remainder, decompressed) =
+        parser::decompress_bounded(&buffer, options.max_decompressed_size)
+            .map_err(|e| parser::decompressor_error(&buffer, e))?;
+    parser::ensure_consumed(&buffer, remainder)?;
+
+    Ok(decompressed)
+}
+
+/// Decompresses the `CompressedContainer` starting at `offset` into `data`, writing at
+/// most `max_output` bytes of decompressed data to `output`.
+#[cfg(feature = "std")]
+fn decompress_from(
+    data: &[u8],
+    offset: usize,
+    output: &mut Vec<u8>,
+    max_output: usize,
+) -> Result<()> {
+    // `offset` is read from the *dir* stream, and can point past the end of the data.
+    let data = data.get(offset..).ok_or(Error::Decompressor { offset })?;
+    let (remainder, _) = parser::decompress_into(data, output, max_output)
+        .map_err(|e| parser::decompressor_error(data, e))?;
+    parser::ensure_consumed(data, remainder)
+}
+
+/// Returns whether the CFB paths `a` and `b` refer to the same entry. Entry names are
+/// compared case-insensitively.
+#[cfg(feature = "std")]
+fn is_same_path(a: &Path, b: &Path) -> bool {
+    a.to_string_lossy()
+        .eq_ignore_ascii_case(&b.to_string_lossy())
+}
+
+/// Returns the CFB path of a module stream of the project stored below `root`.
+///
+/// The module's `stream_name` is read from the *dir* stream, and may contain characters
+/// that are interpreted as path separators (or `.`/`..` path components) when joined to
+/// the VBA storage path. The resulting path would silently refer to a different entry in
+/// the CFB. Such stream names are rejected with an [`Error::InvalidStreamName`] instead.
+#[cfg(feature = "std")]
+fn module_stream_path(root: &Path, stream_name: &str) -> Result<PathBuf> {
+    let mut components = Path::new(stream_name).components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(name)), None) if name == stream_name => {}
+        _ => return Err(Error::InvalidStreamName(stream_name.to_owned())),
+    }
+
+    // No path separator normalization is done in the process; this is intentional.
+    let path = root.join("VBA").join(stream_name);
+    trace!("Stream {:?} resolved to {}", stream_name, path.display());
+    Ok(path)
+}
+
+/// Opens a VBA project.
+///
+/// This function consumes `raw` and returns a [`Project`] struct on success, populated
+/// with data from the parsed binary input.
+///
+/// This is a convenience function that uses default options. Use [`OpenOptions`] to
+/// control how the project is opened.
+#[cfg(feature = "std")]
+pub fn open_project(raw: Vec<u8>) -> Result<Project> {
+    OpenOptions::new().open(raw)
+}
+
+/// Opens a VBA project, reporting progress to `observer`.
+///
+/// This is a convenience function that uses default options. See
+/// [`OpenOptions::open_with_observer`] for details.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::fs::read;
+/// use ovba::{open_project_with_observer, ParseEvent};
+///
+/// let data = read("vbaProject.bin")?;
+/// let project = open_project_with_observer(data, &mut |event| {
+///     if let ParseEvent::ModuleParsed { name } = event {
+///         eprintln!("Parsed module {}", name);
+///     }
+/// })?;
+/// # Ok::<(), ovba::Error>(())
+/// ```
+#[cfg(feature = "std")]
+pub fn open_project_with_observer(
+    raw: Vec<u8>,
+    observer: &mut dyn FnMut(ParseEvent<'_>),
+) -> Result<Project> {
+    OpenOptions::new().open_with_observer(raw, observer)
+}
+
+/// Names of the streams holding a VBA project's digital signatures, in order of
+/// preference.
+#[cfg(feature = "std")]
+const SIGNATURE_STREAMS: [&str; 3] = [
+    "\u{5}DigitalSignatureExt",
+    "\u{5}DigitalSignatureEx",
+    "\u{5}DigitalSignature",
+];
+
+/// Panic message for accessing a `Project`'s container that failed to reopen in
+/// [`Project::write_to`].
+#[cfg(feature = "std")]
+const CONTAINER_TAKEN: &str = "the container failed to reopen in `Project::write_to`";
+
+/// Returns the path of the first *VBA* storage in `container`.
+///
+/// A *VBA* storage is identified by the presence of both a *dir* and a *_VBA_PROJECT*
+/// stream, irrespective of the storage's name. Host applications store the VBA project
+/// at different locations, e.g. `/Macros/VBA` in Word binary documents, or
+/// `/_VBA_PROJECT_CUR/VBA` in Excel binary workbooks. The storage tree is searched in
+/// preorder.
+///
+/// The project's root path, as expected by [`open_project_with_path`], is the parent of
+/// the returned path. Use [`Project::discover_projects`] to find all VBA projects in a
+/// container.
+#[cfg(feature = "std")]
+pub fn locate_vba_project<R: Read + Seek>(container: &CompoundFile<R>) -> Option<PathBuf> {
+    container
+        .walk()
+        .filter(|entry| entry.is_storage())
+        .map(|entry| entry.path().to_path_buf())
+        .find(|path| {
+            container.is_stream(path.join("dir")) && container.is_stream(path.join("_VBA_PROJECT"))
+        })
+}
+
+/// Opens a VBA project from `reader`.
+///
+/// This is the equivalent of [`open_project`] for arbitrary readers. It allows opening
+/// projects stored in large documents without reading the entire document into memory
+/// first.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::fs::File;
+/// use ovba::open_project_from;
+///
+/// let file = File::open("vbaProject.bin")?;
+/// let project = open_project_from(file)?;
+/// # Ok::<(), ovba::Error>(())
+/// ```
+#[cfg(feature = "std")]
+pub fn open_project_from<R: Read + Seek>(reader: R) -> Result<Project<R>> {
+    OpenOptions::new().open_from(reader)
+}
+
+/// Opens a VBA project from a borrowed buffer.
+///
+/// Unlike [`open_project`], this doesn't take ownership of the data, and doesn't copy
+/// it either. Streams are read from `data` directly. This avoids holding a second copy of
+/// large documents in memory, e.g. when parsing a buffer shared with JavaScript on
+/// `wasm32` targets. The returned [`Project`] borrows from `data`.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::fs::read;
+/// use ovba::open_project_bytes;
+///
+/// let data = read("vbaProject.bin")?;
+/// let project = open_project_bytes(&data)?;
+/// # Ok::<(), ovba::Error>(())
+/// ```
+#[cfg(feature = "std")]
+pub fn open_project_bytes(data: &[u8]) -> Result<Project<Cursor<&[u8]>>> {
+    open_project_from(Cursor::new(data))
+}
+
+/// Opens the VBA project stored below `root` in the CFB container read from `reader`.
+///
+/// This is required for VBA projects embedded in binary Office documents, where the
+/// *VBA* storage isn't located at the root of the container. See
+/// [`OpenOptions::open_with_path`] for details.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::fs::File;
+/// use ovba::open_project_with_path;
+///
+/// let file = File::open("document.doc")?;
+/// let project = open_project_with_path("/Macros", file)?;
+/// # Ok::<(), ovba::Error>(())
+/// ```
+#[cfg(feature = "std")]
+pub fn open_project_with_path<P, R>(root: P, reader: R) -> Result<Project<R>>
+where
+    P: AsRef<Path>,
+    R: Read + Seek,
+{
+    OpenOptions::new().open_with_path(root, reader)
+}
+
+/// Opens the VBA project stored below `root` in an already opened CFB `container`.
+///
+/// See [`OpenOptions::open_with_container`] for details.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::{fs::File, path::Path};
+/// use cfb::CompoundFile;
+/// use ovba::open_project_with_container;
+///
+/// let container = CompoundFile::open(File::open("document.doc")?)?;
+/// // Inspect the container using the `cfb` crate API
+/// let project = open_project_with_container(container, Path::new("/Macros"))?;
+/// # Ok::<(), ovba::Error>(())
+/// ```
+#[cfg(feature = "std")]
+pub fn open_project_with_container<R: Read + Seek>(
+    container: CompoundFile<R>,
+    root: &Path,
+) -> Result<Project<R>> {
+    OpenOptions::new().open_with_container(container, root)
+}
+
+/// Opens a VBA project from a CFB container that starts at `offset` into `reader`.
+///
+/// This is useful for containers carved from a larger blob, where the container's offset
+/// is known. The data from `offset` to the end of `reader` is read into memory and
+/// passed to [`open_project`].
+///
+/// # Errors
+///
+/// Returns an [`Error::Io`] error if seeking to `offset` or reading from `reader` fails.
+#[cfg(feature = "std")]
+pub fn open_project_reader_at<R: Read + Seek>(mut reader: R, offset: u64) -> Result<Project> {
+    reader.seek(SeekFrom::Start(offset))?;
+    let mut raw = Vec::new();
+    reader.read_to_end(&mut raw)?;
+    open_project(raw)
+}
+
+/// Reads the decompressed *dir* stream of the VBA project in the CFB container read from
+/// `reader`.
+///
+/// The VBA project is located the same way [`OpenOptions::open_from`] does. The *dir*
+/// stream is decompressed, but not parsed, so this succeeds for projects that fail to
+/// open with an [`Error::Parser`] error. The error's offset refers to the returned data.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::fs::File;
+///
+/// let dir = ovba::raw_dir_from_reader(File::open("vbaProject.bin")?)?;
+/// for record in ovba::iter_dir_records(&dir) {
+///     let record = record?;
+///     println!("0x{:04X}: {:02X?}", record.id, record.payload);
+/// }
+/// # Ok::<(), ovba::Error>(())
+/// ```
+#[cfg(feature = "std")]
+pub fn raw_dir_from_reader<R: Read + Seek>(reader: R) -> Result<Vec<u8>> {
+    let mut container = CompoundFile::open(reader).map_err(Error::Cfb)?;
+    let root = default_root(&container);
+    read_dir_stream(&mut container, &root, &OpenOptions::new())
+}
+
+/// Returns an iterator over the raw records of a decompressed *dir* stream.
+///
+/// Records are split according to the common `Id` (`u16`), `Size` (`u32`), `Data`
+/// layout. The `PROJECTVERSION` record (ID `0x0009`), whose `Size` field doesn't cover
+/// its data, is special-cased and reported with its 6 bytes of data as the payload.
+/// Record payloads are not interpreted otherwise, and the sequence of records isn't
+/// validated.
+///
+/// The iterator ends after the last record, or after yielding the first error.
+///
+/// # Examples
+///
+/// ```rust
+/// // PROJECTSYSKIND record, followed by the dir stream's terminator
+/// let dir = b"\x01\x00\x04\x00\x00\x00\x01\x00\x00\x00\x10\x00\x00\x00\x00\x00";
+/// let ids = ovba::iter_dir_records(dir)
+///     .map(|record| record.map(|record| record.id))
+///     .collect::<ovba::Result<Vec<_>>>()?;
+/// assert_eq!(ids, [0x0001, 0x0010]);
+/// # Ok::<(), ovba::Error>(())
+/// ```
+pub fn iter_dir_records(decompressed_dir: &[u8]) -> impl Iterator<Item = Result<DirRecord<'_>>> {
+    let mut i = decompressed_dir;
+    core::iter::from_fn(move || {
+        if i.is_empty() {
+            return None;
+        }
+        match parser::dir_record(i) {
+            Ok((remainder, record)) => {
+                i = remainder;
+                Some(Ok(record))
+            }
+            Err(e) => {
+                i = &[];
+                Some(Err(parser::parser_error(decompressed_dir, e)))
+            }
+        }
     })
 }
 
+/// Predicts the size of the decompressed data of a `CompressedContainer`.
+///
+/// The prediction is computed by walking the container's chunk headers, without decoding
+/// any token data. It is exact for uncompressed chunks. Compressed chunks are accounted
+/// for with their maximum decompressed size of 4096 bytes, making the result an upper
+/// bound in general.
+///
+/// This is useful to reject inputs that would decompress to an unreasonable size before
+/// allocating any memory for the output.
+///
+/// # Examples
+///
+/// ```rust
+/// // A single uncompressed chunk holding 4096 bytes
+/// let mut data = vec![0x01, 0xff, 0x3f];
+/// data.extend_from_slice(&[b'A'; 4096]);
+/// assert_eq!(ovba::predicted_decompressed_size(&data)?, 4096);
+/// # Ok::<(), ovba::Error>(())
+/// ```
+pub fn predicted_decompressed_size(data: &[u8]) -> Result<usize> {
+    let (_, size) =
+        parser::predicted_size(data).map_err(|e| parser::decompressor_error(data, e))?;
+    Ok(size)
+}
+
+/// Parses a decompressed *dir* stream.
+///
+/// This runs the *dir* stream parser used by [`open_project`] over `decompressed`,
+/// without accessing a CFB container. It suits client code that extracts and
+/// decompresses the *dir* stream by other means, and is available without the `std`
+/// feature. [`decompress`] decompresses the stream's raw contents.
+///
+/// This is a convenience function that uses default options. Use
+/// [`OpenOptions::parse_dir`] to control how the stream is parsed.
+///
+/// # Errors
+///
+/// Returns an [`Error::Parser`] or [`Error::Truncated`] error if `decompressed` isn't a
+/// valid *dir* stream, with offsets relative to the start of `decompressed`. Returns an
+/// [`Error::Encoding`] error if the project's code page cannot be mapped to an encoding.
+pub fn parse_dir(decompressed: &[u8]) -> Result<ProjectMetadata> {
+    let (metadata, _) = OpenOptions::new().parse_dir(decompressed)?;
+    Ok(metadata)
+}
+
+/// Decompresses a `CompressedContainer`.
+///
+/// This is the counterpart to [`compress`], and doesn't limit the size of the
+/// decompressed data. `CompressedContainer`s decompress to at most a few thousand times
+/// their size. Use [`predicted_decompressed_size`] to reject large inputs up front.
+///
+/// # Examples
+///
+/// ```rust
+/// let data = ovba::compress(b"Attribute VB_Name = \"Module1\"");
+/// assert_eq!(ovba::decompress(&data)?, b"Attribute VB_Name = \"Module1\"");
+/// # Ok::<(), ovba::Error>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns an [`Error::Decompressor`] error if `data` isn't a valid
+/// `CompressedContainer`.
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>> {
+    let (remainder, decompressed) = parser::decompress_bounded(data, usize::MAX)
+        .map_err(|e| parser::decompressor_error(data, e))?;
+    parser::ensure_consumed(data, remainder)?;
+    Ok(decompressed)
+}
+
 #[cfg(test)]
+mod alloc_tests;
+#[cfg(all(test, feature = "std"))]
 mod tests;
@@ -1,45 +1,278 @@
 #![forbid(unsafe_code)]
 
 use crate::{
-    Information, Module, ModuleType, Reference, ReferenceControl, ReferenceOriginal,
-    ReferenceProject, ReferenceRegistered, SysKind,
+    DirRecord, Information, Module, ModuleType, OpenOptions, ParseErrorKind, ParseEvent,
+    ProjectMetadata, Reference, ReferenceControl, ReferenceOriginal, ReferenceProject,
+    ReferenceRegistered, SysKind, Warning,
 };
+use alloc::{borrow::Cow, collections::BTreeMap, string::String, vec, vec::Vec};
 use codepage::to_encoding;
-use encoding_rs::{CoderResult, UTF_16LE};
+use core::convert::TryInto;
+use encoding_rs::UTF_16LE;
 use nom::{
     bytes::complete::{tag, take},
     combinator::opt,
     error::{ErrorKind, ParseError},
-    multi::length_data,
     number::complete::{le_u16, le_u32, le_u8},
     sequence::{preceded, tuple},
     Err::Error,
-    IResult,
+    IResult, Offset,
 };
+#[cfg(feature = "std")]
+use std::io::{self, Read};
 
-// This used to be part of the public interface prior to flattening this out into the
-// [`Project`] struct.
-// TODO: Re-evaluate whether this struct is strictly necessary, or can be removed.
-/// Specifies information for the VBA project, including project information, project
-/// references, and modules.
-#[derive(Debug)]
-pub(crate) struct ProjectInformation {
-    /// Specifies version-independent information for the VBA project.
-    pub information: Information,
-    /// Specifies the external references of the VBA project.
-    pub references: Vec<Reference>,
-    /// Specifies the modules in the project.
-    pub modules: Vec<Module>,
-}
-
-// TODO: Make this error private by translating to a crate-level error type
-//       at the public parser interface.
+// This error is private to the parser. It is translated into the crate-level error
+// type by `parser_error` and `decompressor_error` at the parser interface.
 #[derive(Debug, PartialEq)]
 pub(crate) enum FormatError<I> {
-    UnexpectedValue,
+    UnexpectedValue(I),
+    /// Length-prefixed data extends past the end of the input. Holds the location of the
+    /// data, and its declared length.
+    Truncated(I, usize),
+    /// Input remains after the final record.
+    TrailingData(I),
+    /// A string cannot be decoded, because the code page cannot be mapped to an encoding.
+    Encoding(u16),
     Nom(I, ErrorKind),
 }
 
+impl FormatError<&[u8]> {
+    /// Returns the offset of the error location relative to `input`, along with the
+    /// respective error kind.
+    fn locate(&self, input: &[u8]) -> (usize, ParseErrorKind) {
+        match self {
+            FormatError::UnexpectedValue(i) => (input.offset(i), ParseErrorKind::UnexpectedValue),
+            FormatError::Truncated(i, _) => (input.offset(i), ParseErrorKind::UnexpectedEof),
+            FormatError::TrailingData(i) => (input.offset(i), ParseErrorKind::TrailingData),
+            // Reported as `Error::Encoding` by `parser_error`. The error isn't tied to a
+            // location in the input.
+            FormatError::Encoding(_) => (0, ParseErrorKind::UnexpectedValue),
+            FormatError::Nom(i, kind) => {
+                let kind = match kind {
+                    ErrorKind::Tag => ParseErrorKind::UnexpectedTag,
+                    ErrorKind::Eof | ErrorKind::Complete => ParseErrorKind::UnexpectedEof,
+                    _ => ParseErrorKind::Other,
+                };
+                (input.offset(i), kind)
+            }
+        }
+    }
+}
+
+/// Translates a *dir* stream parser error into the public error type.
+///
+/// `input` is the full input passed to the parser.
+pub(crate) fn parser_error(input: &[u8], err: nom::Err<FormatError<&[u8]>>) -> crate::Error {
+    let (offset, kind) = match err {
+        nom::Err::Error(FormatError::Truncated(i, needed))
+        | nom::Err::Failure(FormatError::Truncated(i, needed)) => {
+            return crate::Error::Truncated {
+                offset: input.offset(i),
+                needed,
+                available: i.len(),
+            }
+        }
+        nom::Err::Error(FormatError::Encoding(code_page))
+        | nom::Err::Failure(FormatError::Encoding(code_page)) => {
+            return crate::Error::Encoding { code_page }
+        }
+        nom::Err::Error(e) | nom::Err::Failure(e) => e.locate(input),
+        nom::Err::Incomplete(_) => (input.len(), ParseErrorKind::UnexpectedEof),
+    };
+    crate::Error::Parser {
+        offset,
+        kind,
+        record: None,
+    }
+}
+
+/// Verifies that a parser consumed all of its `input`.
+///
+/// `remainder` is the unconsumed input returned by the parser. Leftover data usually
+/// indicates a structure that isn't supported, and is reported as an
+/// [`Error::Parser`](crate::Error::Parser) of kind [`ParseErrorKind::TrailingData`],
+/// located at the start of the leftover data.
+pub(crate) fn ensure_consumed(input: &[u8], remainder: &[u8]) -> crate::Result<()> {
+    if remainder.is_empty() {
+        Ok(())
+    } else {
+        Err(crate::Error::Parser {
+            offset: input.offset(remainder),
+            kind: ParseErrorKind::TrailingData,
+            record: None,
+        })
+    }
+}
+
+/// Translates a *dir* stream parser error into the public error type.
+///
+/// Same as [`parser_error`], and additionally identifies the record that contains the
+/// error location.
+pub(crate) fn dir_parser_error(input: &[u8], err: nom::Err<FormatError<&[u8]>>) -> crate::Error {
+    match parser_error(input, err) {
+        // Trailing data isn't part of any record.
+        crate::Error::Parser { offset, kind, .. } if kind != ParseErrorKind::TrailingData => {
+            crate::Error::Parser {
+                offset,
+                kind,
+                record: record_at(input, offset),
+            }
+        }
+        err => err,
+    }
+}
+
+/// Returns the name of the *dir* stream record that contains `offset`.
+///
+/// Records are walked from the start of `input`. If a record cannot be read in full, the
+/// record is identified by its ID alone.
+fn record_at(input: &[u8], offset: usize) -> Option<&'static str> {
+    let mut i = input;
+    loop {
+        let start = input.offset(i);
+        if start > offset {
+            return None;
+        }
+        match dir_record(i) {
+            Ok((remainder, record)) => {
+                if offset < input.offset(remainder) {
+                    return record_name(record.id);
+                }
+                i = remainder;
+            }
+            Err(_) => {
+                return le_u16::<_, FormatError<_>>(i)
+                    .ok()
+                    .and_then(|(_, id)| record_name(id))
+            }
+        }
+    }
+}
+
+/// Returns the name of the *dir* stream record with ID `id`.
+///
+/// Records that are part of a compound record in the specification (such as the Unicode
+/// variant of a string) are reported using the compound record's name.
+fn record_name(id: u16) -> Option<&'static str> {
+    let name = match id {
+        0x0001 => "PROJECTSYSKIND",
+        0x004A => "PROJECTCOMPATVERSION",
+        0x0002 => "PROJECTLCID",
+        0x0014 => "PROJECTLCIDINVOKE",
+        0x0003 => "PROJECTCODEPAGE",
+        0x0004 => "PROJECTNAME",
+        0x0005 | 0x0040 => "PROJECTDOCSTRING",
+        0x0006 | 0x003D => "PROJECTHELPFILEPATH",
+        0x0007 => "PROJECTHELPCONTEXT",
+        0x0008 => "PROJECTLIBFLAGS",
+        0x0009 => "PROJECTVERSION",
+        0x000C | 0x003C => "PROJECTCONSTANTS",
+        0x0016 | 0x003E => "REFERENCENAME",
+        0x0033 => "REFERENCEORIGINAL",
+        0x002F | 0x0030 => "REFERENCECONTROL",
+        0x000D => "REFERENCEREGISTERED",
+        0x000E => "REFERENCEPROJECT",
+        0x000F => "PROJECTMODULES",
+        0x0013 => "PROJECTCOOKIE",
+        0x0019 => "MODULENAME",
+        0x0047 => "MODULENAMEUNICODE",
+        0x001A | 0x0032 => "MODULESTREAMNAME",
+        0x001C | 0x0048 => "MODULEDOCSTRING",
+        0x0031 => "MODULEOFFSET",
+        0x001E => "MODULEHELPCONTEXT",
+        0x002C => "MODULECOOKIE",
+        0x0021 | 0x0022 => "MODULETYPE",
+        0x0025 => "MODULEREADONLY",
+        0x0028 => "MODULEPRIVATE",
+        0x002B => "MODULE",
+        0x0010 => "Terminator",
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// Translates a decompressor error into the public error type.
+///
+/// `input` is the full `CompressedContainer` passed to the decompressor.
+pub(crate) fn decompressor_error(input: &[u8], err: nom::Err<FormatError<&[u8]>>) -> crate::Error {
+    let offset = match err {
+        nom::Err::Error(e) | nom::Err::Failure(e) => e.locate(input).0,
+        nom::Err::Incomplete(_) => input.len(),
+    };
+    crate::Error::Decompressor { offset }
+}
+
+/// A callback that receives `ParseEvent`s.
+pub(crate) type Observer<'o> = &'o mut dyn FnMut(ParseEvent<'_>);
+
+/// Parser configuration and diagnostics shared across the *dir* stream parsers.
+pub(crate) struct ParseState<'o> {
+    /// Skip unknown, length-prefixed records instead of rejecting them.
+    pub forward_compat: bool,
+    /// Tolerate deviations from the specification that don't affect the parsed data.
+    pub lenient: bool,
+    /// Decode strings using `FALLBACK_CODE_PAGE` if the project's code page cannot be
+    /// mapped to an encoding.
+    pub fallback_encoding: bool,
+    /// Verify that records duplicating other records' data match.
+    pub verify_duplicates: bool,
+    /// Decode strings using this code page instead of the project's code page.
+    pub code_page: Option<u16>,
+    /// Diagnostics collected while parsing.
+    pub warnings: Vec<Warning>,
+    /// Receives progress events while parsing.
+    pub observer: Option<Observer<'o>>,
+    /// Length of the full input. Used to report record offsets.
+    input_len: usize,
+}
+
+impl<'o> ParseState<'o> {
+    pub(crate) fn new(options: &OpenOptions, input: &[u8]) -> Self {
+        Self {
+            forward_compat: options.forward_compat,
+            lenient: options.lenient,
+            fallback_encoding: options.fallback_encoding,
+            verify_duplicates: options.verify_duplicates,
+            code_page: options.code_page,
+            warnings: Vec::new(),
+            observer: None,
+            input_len: input.len(),
+        }
+    }
+
+    /// Passes `event` to the observer, if any.
+    fn emit(&mut self, event: ParseEvent<'_>) {
+        if let Some(observer) = &mut self.observer {
+            observer(event);
+        }
+    }
+
+    /// Returns the offset of `i` relative to the start of the full input.
+    fn offset(&self, i: &[u8]) -> usize {
+        self.input_len - i.len()
+    }
+
+    /// Reports a `Warning::MismatchedDuplicate` for the record at the start of `record`,
+    /// if duplicates are verified and `matches` returns `false`.
+    fn verify_duplicate(&mut self, record: &[u8], matches: impl FnOnce() -> bool) {
+        if self.verify_duplicates && !matches() {
+            self.warnings.push(Warning::MismatchedDuplicate {
+                id: u16::from_le_bytes([record[0], record[1]]),
+                offset: self.offset(record),
+            });
+        }
+    }
+}
+
+/// Returns whether `unicode` holds the UTF-16LE encoding of `s`.
+fn is_utf16_of(unicode: &[u8], s: &str) -> bool {
+    unicode.len() & 1 == 0
+        && unicode
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .eq(s.encode_utf16())
+}
+
 impl<I> ParseError<I> for FormatError<I> {
     fn from_error_kind(input: I, kind: ErrorKind) -> Self {
         FormatError::Nom(input, kind)
@@ -49,13 +282,26 @@ impl<I> ParseError<I> for FormatError<I> {
     }
 }
 
-fn uncompressed_chunk_parser(i: &[u8]) -> IResult<&[u8], Vec<u8>, FormatError<&[u8]>> {
-    Ok((&[], i.to_vec()))
+fn uncompressed_chunk_parser<'a>(
+    i: &'a [u8],
+    output: &mut Vec<u8>,
+) -> IResult<&'a [u8], (), FormatError<&'a [u8]>> {
+    output.extend_from_slice(i);
+    Ok((&[], ()))
 }
 
-fn compressed_chunk_parser(i: &[u8]) -> IResult<&[u8], Vec<u8>, FormatError<&[u8]>> {
-    // Initialize output storage; Chunks are at most 4096 decompressed bytes
-    let mut result = Vec::<u8>::with_capacity(4096);
+/// Decompresses a compressed chunk, appending the decompressed data to `output`.
+///
+/// CopyTokens are decoded relative to the start of the chunk's data in `output`. Data
+/// already held in `output` is never referenced.
+fn compressed_chunk_parser<'a>(
+    i: &'a [u8],
+    output: &mut Vec<u8>,
+) -> IResult<&'a [u8], (), FormatError<&'a [u8]>> {
+    // Reserve output storage; Chunks are at most 4096 decompressed bytes
+    const MAX_CHUNK_SIZE: usize = 4096;
+    output.reserve(MAX_CHUNK_SIZE);
+    let chunk_start = output.len();
     // Loop until `i` is depleted
     let mut input = i;
     while !input.is_empty() {
@@ -66,101 +312,572 @@ fn compressed_chunk_parser(i: &[u8]) -> IResult<&[u8], Vec<u8>, FormatError<&[u8
         for flag_bit_index in 0..=7 {
             // Return, if we have reached the end of this chunk
             if input.is_empty() {
-                return Ok((input, result));
+                return Ok((input, ()));
             }
             // Determine token type (0b0 == LiteralToken; 0b1 == CopyToken)
             let is_copy_token = (flag_byte & (1 << flag_bit_index)) != 0;
             // Delegate work based on TokenType
             if is_copy_token {
                 // TODO: Move the CopyToken decoder into its own, dedicated parser.
+                let token = input;
                 let (i, copy_token_raw) = le_u16(input)?;
                 input = i;
                 // Calculate length/offset masks
-                let diff = result.len();
-                let mut bit_count = 4_usize;
-                while 1 << bit_count < diff {
-                    bit_count += 1;
-                }
+                let position = output.len() - chunk_start;
+                let bit_count = copy_token_bit_count(position);
                 let length_mask = 0xffff_u16 >> bit_count;
                 let offset_mask = !length_mask;
                 // Calculate length/offset
                 let length = ((copy_token_raw & length_mask) + 3) as usize;
                 let offset = (((copy_token_raw & offset_mask) >> (16 - bit_count)) + 1) as usize;
+                // Reject tokens that refer to data preceding the chunk, or that would
+                // exceed the maximum chunk size. Either one is only expected with
+                // malformed input.
+                if offset > position || position + length > MAX_CHUNK_SIZE {
+                    return Err(Error(FormatError::UnexpectedValue(token)));
+                }
                 // Copy `length` bytes starting at index `offset`
-                for index in result.len() - offset..result.len() - offset + length {
-                    result.push(result[index]);
+                let start = output.len() - offset;
+                for index in start..start + length {
+                    output.push(output[index]);
                 }
             } else {
                 // LiteralToken -> Copy token from input stream
+                if output.len() - chunk_start == MAX_CHUNK_SIZE {
+                    return Err(Error(FormatError::UnexpectedValue(input)));
+                }
                 let (i, byte) = le_u8(input)?;
                 input = i;
-                result.push(byte);
+                output.push(byte);
             }
         }
     }
 
-    Ok((input, result))
+    Ok((input, ()))
+}
+
+/// Returns the number of bits used to encode the offset of a CopyToken, given the
+/// position of the token's output in the decompressed chunk.
+fn copy_token_bit_count(position: usize) -> usize {
+    let mut bit_count = 4_usize;
+    while 1 << bit_count < position {
+        bit_count += 1;
+    }
+    bit_count
+}
+
+/// Returns the length of a chunk's data, following the chunk header `header_raw`.
+/// `available` is the number of bytes following the header.
+///
+/// The data of a compressed chunk is sized by the header. The data of an uncompressed
+/// chunk is always 4096 bytes (\[MS-OVBA\] section 2.4.1.3.3), irrespective of the size
+/// stored in the header. A final uncompressed chunk that holds less data is accepted, and
+/// consumes the remaining input.
+fn chunk_data_length(header_raw: u16, available: usize) -> usize {
+    const UNCOMPRESSED_CHUNK_SIZE: usize = 4096;
+    let flag = ((header_raw >> 15) & 0b1) != 0;
+    if flag {
+        (header_raw & 0xfff) as usize + 1
+    } else {
+        available.min(UNCOMPRESSED_CHUNK_SIZE)
+    }
 }
 
-fn chunk_parser(i: &[u8]) -> IResult<&[u8], Vec<u8>, FormatError<&[u8]>> {
+/// Decompresses a chunk, appending the decompressed data to `output`.
+fn chunk_parser<'a>(
+    input: &'a [u8],
+    output: &mut Vec<u8>,
+) -> IResult<&'a [u8], (), FormatError<&'a [u8]>> {
     // CompressedChunkHeader (12 bits: size minus 3; 3 bits: 0b110; 1 bit: flag)
     // Delegate to specific parser (compressed/uncompressed) depending on the `flag`
-    let (i, header_raw) = le_u16(i)?;
+    let (i, header_raw) = le_u16(input)?;
     // Check header magic (0b110) in bit positions 12..=14
     if (header_raw >> 12) & 0b111 != 0b011 {
-        return Err(Error(FormatError::UnexpectedValue));
+        return Err(Error(FormatError::UnexpectedValue(input)));
     }
     // Extract compressed/uncompressed flag
     let flag = ((header_raw >> 15) & 0b1) != 0;
     // Extract length
-    let length = (header_raw & 0xfff) as usize + 1;
+    let length = chunk_data_length(header_raw, i.len());
 
+    if length > i.len() {
+        return Err(Error(FormatError::Truncated(i, length)));
+    }
     let (chunk, remainder) = i.split_at(length);
     if flag {
-        Ok((remainder, compressed_chunk_parser(chunk)?.1))
+        compressed_chunk_parser(chunk, output)?;
     } else {
-        Ok((remainder, uncompressed_chunk_parser(chunk)?.1))
+        uncompressed_chunk_parser(chunk, output)?;
     }
+    Ok((remainder, ()))
 }
 
-/// Decompress a CompressedContainer.
+/// Decompress a CompressedContainer, without limiting the output size.
+#[cfg(test)]
 pub(crate) fn decompress(i: &[u8]) -> IResult<&[u8], Vec<u8>, FormatError<&[u8]>> {
+    decompress_bounded(i, usize::MAX)
+}
+
+/// Decompress a CompressedContainer, producing at most `max_output` bytes.
+///
+/// Decompression fails with an `UnexpectedValue` error at the start of the chunk that
+/// exceeds `max_output`.
+pub(crate) fn decompress_bounded(
+    i: &[u8],
+    max_output: usize,
+) -> IResult<&[u8], Vec<u8>, FormatError<&[u8]>> {
+    let mut output = Vec::new();
+    let (i, _) = decompress_into(i, &mut output, max_output)?;
+    Ok((i, output))
+}
+
+/// Decompress a CompressedContainer into `output`, producing at most `max_output` bytes.
+///
+/// `output` is cleared first. Its allocation is reused, and grown one chunk at a time as
+/// needed.
+pub(crate) fn decompress_into<'a>(
+    i: &'a [u8],
+    output: &mut Vec<u8>,
+    max_output: usize,
+) -> IResult<&'a [u8], (), FormatError<&'a [u8]>> {
     const COMPRESSED_CONTAINER_SIGNATURE: &[u8] = &[0x01];
-    let (i, _) = tag(COMPRESSED_CONTAINER_SIGNATURE)(i)?;
-
-    // This is the main `Chunk` parser:
-    // * It parses 1 or more chunks, returning a `Vec<u8>` with decoded content.
-    // * It appends the contents of the most recent `Chunk` to the existing decoded stream.
-    // * If all data has been consumed, return an `Ok()` value.
-    nom::combinator::all_consuming(nom::multi::fold_many1(
-        chunk_parser,
-        Vec::new,
-        |mut acc: Vec<_>, data| {
-            acc.extend(data);
-            acc
-        },
-    ))(i)
+    output.clear();
+    let (mut i, _) = tag(COMPRESSED_CONTAINER_SIGNATURE)(i)?;
+
+    // Parse 1 or more chunks, appending the decoded content to `output`, until all data
+    // has been consumed. A malformed chunk other than the first is reported at the start
+    // of the chunk.
+    let mut first_chunk = true;
+    while first_chunk || !i.is_empty() {
+        match chunk_parser(i, output) {
+            // Chunks decompress to at most 4096 bytes, which bounds the overshoot.
+            Ok(_) if output.len() > max_output => {
+                return Err(Error(FormatError::UnexpectedValue(i)));
+            }
+            Ok((remainder, _)) => i = remainder,
+            Err(Error(e)) if first_chunk => return Err(Error(e)),
+            Err(Error(_)) => return Err(Error(FormatError::Nom(i, ErrorKind::Eof))),
+            Err(e) => return Err(e),
+        }
+        first_chunk = false;
+    }
+    Ok((i, ()))
+}
+
+/// Decompress the leading part of a CompressedContainer.
+///
+/// Chunks are decompressed until the output holds at least `max_len` bytes, or the input
+/// is exhausted. Remaining chunks are neither decompressed nor validated. The output is
+/// truncated to `max_len` bytes.
+#[cfg(feature = "std")]
+pub(crate) fn decompress_prefix(
+    i: &[u8],
+    max_len: usize,
+) -> IResult<&[u8], Vec<u8>, FormatError<&[u8]>> {
+    const COMPRESSED_CONTAINER_SIGNATURE: &[u8] = &[0x01];
+    let (mut i, _) = tag(COMPRESSED_CONTAINER_SIGNATURE)(i)?;
+
+    let mut result = Vec::new();
+    while result.len() < max_len && !i.is_empty() {
+        let (remainder, _) = chunk_parser(i, &mut result)?;
+        i = remainder;
+    }
+    result.truncate(max_len);
+    Ok((i, result))
+}
+
+/// Compresses `data` into a `CompressedContainer`.
+///
+/// This implements the compression algorithm described in \[MS-OVBA\] section 2.4.1.3.
+/// The input is split into chunks of 4096 bytes. Each chunk is encoded using CopyTokens
+/// for repeated sequences of at least 3 bytes, and LiteralTokens otherwise. A chunk that
+/// doesn't shrink this way is stored uncompressed instead.
+///
+/// The result decompresses to `data`. Unlike the reference algorithm, the final chunk
+/// isn't padded with zero bytes when it is stored uncompressed.
+///
+/// # Examples
+///
+/// ```rust
+/// use ovba::compress;
+///
+/// let data = b"Attribute VB_Name = \"Module1\"\r\n".repeat(10);
+/// let container = compress(&data);
+/// assert!(container.len() < data.len());
+/// ```
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    const CHUNK_SIZE: usize = 4096;
+    let mut result = vec![0x01];
+    if data.is_empty() {
+        // A container holds at least one chunk. Emit a compressed chunk consisting of a
+        // single FlagByte that isn't followed by any tokens.
+        result.extend_from_slice(&[0x00, 0xb0, 0x00]);
+        return result;
+    }
+
+    for chunk in data.chunks(CHUNK_SIZE) {
+        let payload = compress_chunk(chunk);
+        if payload.len() <= CHUNK_SIZE {
+            let header = 0xb000_u16 | (payload.len() - 1) as u16;
+            result.extend_from_slice(&header.to_le_bytes());
+            result.extend(payload);
+        } else {
+            // The size of an uncompressed chunk is fixed, irrespective of the data
+            // length.
+            let header = 0x3fff_u16;
+            result.extend_from_slice(&header.to_le_bytes());
+            result.extend_from_slice(chunk);
+        }
+    }
+    result
+}
+
+/// Encodes the decompressed `chunk` as a sequence of FlagBytes and tokens.
+fn compress_chunk(chunk: &[u8]) -> Vec<u8> {
+    let mut result = Vec::with_capacity(chunk.len());
+    // Positions of preceding data, indexed by the 3 bytes starting there. Matches
+    // shorter than 3 bytes aren't encoded as CopyTokens.
+    let mut candidates = BTreeMap::<&[u8], Vec<usize>>::new();
+    let mut position = 0;
+    while position < chunk.len() {
+        let flag_byte_index = result.len();
+        result.push(0x00);
+        for flag_bit_index in 0..=7 {
+            if position == chunk.len() {
+                break;
+            }
+            let length = match longest_match(chunk, position, &candidates) {
+                Some((offset, length)) => {
+                    let bit_count = copy_token_bit_count(position);
+                    let copy_token = ((offset - 1) << (16 - bit_count)) | (length - 3);
+                    result.extend_from_slice(&(copy_token as u16).to_le_bytes());
+                    result[flag_byte_index] |= 1 << flag_bit_index;
+                    length
+                }
+                None => {
+                    result.push(chunk[position]);
+                    1
+                }
+            };
+            for start in position..position + length {
+                if let Some(prefix) = chunk.get(start..start + 3) {
+                    candidates.entry(prefix).or_default().push(start);
+                }
+            }
+            position += length;
+        }
+    }
+    result
+}
+
+/// Finds the longest sequence preceding `position` in `chunk` that matches the data at
+/// `position`. Returns the match's offset and length, if a match of at least 3 bytes
+/// exists. Ties are resolved in favor of the closest match.
+fn longest_match(
+    chunk: &[u8],
+    position: usize,
+    candidates: &BTreeMap<&[u8], Vec<usize>>,
+) -> Option<(usize, usize)> {
+    let max_length = (0xffff_usize >> copy_token_bit_count(position)) + 3;
+    let end = chunk.len().min(position + max_length);
+    let candidates = chunk
+        .get(position..position + 3)
+        .and_then(|prefix| candidates.get(prefix))?;
+
+    let mut best = (0, 0);
+    for &candidate in candidates.iter().rev() {
+        let length = chunk[candidate..]
+            .iter()
+            .zip(&chunk[position..end])
+            .take_while(|(a, b)| a == b)
+            .count();
+        if length > best.1 {
+            best = (position - candidate, length);
+        }
+        if length == end - position {
+            break;
+        }
+    }
+    if best.1 >= 3 {
+        Some(best)
+    } else {
+        None
+    }
+}
+
+/// A streaming decompressor for `CompressedContainer`s.
+///
+/// Reads a `CompressedContainer` from the underlying reader and decompresses it one chunk
+/// at a time, as data is requested through the [`Read`] implementation. At most a single
+/// decompressed chunk (4096 bytes) is buffered. This allows scanning the beginning of a
+/// large module stream without decompressing all of it.
+///
+/// Malformed input is reported as an [`io::Error`] of kind
+/// [`InvalidData`](io::ErrorKind::InvalidData), wrapping an
+/// [`Error::Decompressor`](crate::Error::Decompressor) that holds the offset into the
+/// `CompressedContainer`. Errors reported by the underlying reader are passed through.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::io::{BufRead, BufReader};
+/// use ovba::Decompressor;
+///
+/// let container: &[u8] = b"\x01\x0A\xB0\x00Sub A()\r\x00\n";
+/// let mut lines = BufReader::new(Decompressor::new(container)).lines();
+/// assert_eq!(lines.next().unwrap()?, "Sub A()");
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct Decompressor<R> {
+    reader: R,
+    /// Offset into the `CompressedContainer` of the next chunk, or 0 if the signature
+    /// hasn't been read yet.
+    offset: usize,
+    /// The most recently decompressed chunk.
+    chunk: Vec<u8>,
+    /// Read position into `chunk`.
+    pos: usize,
+    /// Set once the end of the input has been reached, or decompression failed.
+    done: bool,
+}
+
+#[cfg(feature = "std")]
+impl<R: Read> Decompressor<R> {
+    /// Creates a new decompressor that reads a `CompressedContainer` from `reader`.
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            offset: 0,
+            chunk: Vec::new(),
+            pos: 0,
+            done: false,
+        }
+    }
+
+    /// Consumes the decompressor, returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Decompresses the next chunk into `self.chunk`.
+    ///
+    /// Sets `self.done` if the input is exhausted.
+    fn next_chunk(&mut self) -> io::Result<()> {
+        if self.offset == 0 {
+            let mut signature = [0_u8; 1];
+            if read_full(&mut self.reader, &mut signature)? != 1 || signature != [0x01] {
+                return Err(invalid_container(0));
+            }
+            self.offset = 1;
+        }
+
+        let mut header = [0_u8; 2];
+        match read_full(&mut self.reader, &mut header)? {
+            // A container holds at least one chunk
+            0 if self.offset > 1 => {
+                self.done = true;
+                return Ok(());
+            }
+            2 => {}
+            _ => return Err(invalid_container(self.offset)),
+        }
+        // The remaining input size isn't known up front. The data of an uncompressed chunk
+        // is truncated at the end of the input below.
+        let length = chunk_data_length(u16::from_le_bytes(header), usize::MAX);
+        let mut chunk = header.to_vec();
+        chunk.resize(header.len() + length, 0);
+        let available = read_full(&mut self.reader, &mut chunk[header.len()..])?;
+        chunk.truncate(header.len() + available);
+
+        self.chunk.clear();
+        self.pos = 0;
+        if let Err(e) = chunk_parser(&chunk, &mut self.chunk) {
+            // Don't serve partially decompressed data.
+            self.chunk.clear();
+            let offset = match e {
+                nom::Err::Error(e) | nom::Err::Failure(e) => e.locate(&chunk).0,
+                nom::Err::Incomplete(_) => chunk.len(),
+            };
+            return Err(invalid_container(self.offset + offset));
+        }
+        self.offset += chunk.len();
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read> Read for Decompressor<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.chunk.len() {
+            if self.done || buf.is_empty() {
+                return Ok(0);
+            }
+            if let Err(e) = self.next_chunk() {
+                self.done = true;
+                return Err(e);
+            }
+        }
+        let len = buf.len().min(self.chunk.len() - self.pos);
+        buf[..len].copy_from_slice(&self.chunk[self.pos..self.pos + len]);
+        self.pos += len;
+        Ok(len)
+    }
+}
+
+/// Reads from `reader` until `buf` is full or the end of the input is reached. Returns
+/// the number of bytes read.
+#[cfg(feature = "std")]
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut len = 0;
+    while len < buf.len() {
+        match reader.read(&mut buf[len..]) {
+            Ok(0) => break,
+            Ok(n) => len += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(len)
+}
+
+#[cfg(feature = "std")]
+fn invalid_container(offset: usize) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        crate::Error::Decompressor { offset },
+    )
+}
+
+/// Computes the decompressed size of a CompressedContainer from its chunk headers.
+///
+/// Token data isn't decoded. Compressed chunks contribute their maximum decompressed size
+/// of 4096 bytes, uncompressed chunks contribute the size of their data.
+pub(crate) fn predicted_size(i: &[u8]) -> IResult<&[u8], usize, FormatError<&[u8]>> {
+    const COMPRESSED_CONTAINER_SIGNATURE: &[u8] = &[0x01];
+    let (mut i, _) = tag(COMPRESSED_CONTAINER_SIGNATURE)(i)?;
+    if i.is_empty() {
+        return Err(Error(FormatError::Nom(i, ErrorKind::Many1)));
+    }
+
+    let mut size = 0_usize;
+    while !i.is_empty() {
+        let input = i;
+        let (remainder, header_raw) = le_u16(input)?;
+        if (header_raw >> 12) & 0b111 != 0b011 {
+            return Err(Error(FormatError::UnexpectedValue(input)));
+        }
+        let flag = ((header_raw >> 15) & 0b1) != 0;
+        let length = chunk_data_length(header_raw, remainder.len());
+        if length > remainder.len() {
+            return Err(Error(FormatError::Truncated(remainder, length)));
+        }
+        size += if flag { 4096 } else { length };
+        i = &remainder[length..];
+    }
+    Ok((i, size))
 }
 
 // -------------------------------------------------------------------------
 // -------------------------------------------------------------------------
 
+/// Parses a length-prefixed byte sequence, with the length encoded as a `u32`.
+///
+/// This is the equivalent of `length_data(le_u32)`, except that a declared length that
+/// exceeds the remaining input is reported as `FormatError::Truncated`.
+fn length_data_u32(i: &[u8]) -> IResult<&[u8], &[u8], FormatError<&[u8]>> {
+    let (i, length) = le_u32(i)?;
+    let length = length as usize;
+    if length > i.len() {
+        return Err(Error(FormatError::Truncated(i, length)));
+    }
+    let (data, i) = i.split_at(length);
+    Ok((i, data))
+}
+
 // Several size fields in the binary format have fixed values.
 const U32_FIXED_SIZE_4: &[u8] = &[0x04, 0x00, 0x00, 0x00];
 const U32_FIXED_SIZE_2: &[u8] = &[0x02, 0x00, 0x00, 0x00];
 
-fn parse_syskind(i: &[u8]) -> IResult<&[u8], SysKind, FormatError<&[u8]>> {
+// Record IDs defined by the \[MS-OVBA\] specification for the *dir* stream. Any ID not
+// listed here is considered unknown, and can be skipped in forward-compatibility mode.
+const KNOWN_RECORD_IDS: &[u16] = &[
+    0x0001, 0x0002, 0x0003, 0x0004, 0x0005, 0x0006, 0x0007, 0x0008, 0x0009, 0x000c, 0x000d, 0x000e,
+    0x000f, 0x0010, 0x0013, 0x0014, 0x0016, 0x0019, 0x001a, 0x001c, 0x001e, 0x0021, 0x0022, 0x0025,
+    0x0028, 0x002b, 0x002c, 0x002f, 0x0030, 0x0031, 0x0032, 0x0033, 0x003c, 0x003d, 0x003e, 0x0040,
+    0x0047, 0x0048, 0x004a,
+];
+
+/// Skips over any number of unknown records.
+///
+/// This is a no-op unless forward-compatibility mode is enabled. An unknown record is
+/// only skipped if it follows the common `Id` (`u16`), `Size` (`u32`), `Data` layout, and
+/// the declared `Size` doesn't exceed the remaining input. Every skipped record is
+/// reported as a [`Warning::UnknownRecord`].
+fn skip_unknown_records<'a>(
+    i: &'a [u8],
+    state: &mut ParseState<'_>,
+) -> IResult<&'a [u8], (), FormatError<&'a [u8]>> {
+    let mut i = i;
+    while state.forward_compat {
+        let (_, id) = match le_u16::<_, FormatError<&[u8]>>(i) {
+            Ok(value) => value,
+            Err(_) => break,
+        };
+        if KNOWN_RECORD_IDS.contains(&id) {
+            break;
+        }
+        let record: IResult<_, _, FormatError<&[u8]>> = preceded(le_u16, length_data_u32)(i);
+        let (remainder, payload) = match record {
+            Ok(value) => value,
+            Err(_) => break,
+        };
+        debug!(
+            "Skipped unknown record 0x{:04X} at offset 0x{:X}",
+            id,
+            state.offset(i)
+        );
+        state.warnings.push(Warning::UnknownRecord {
+            id,
+            offset: state.offset(i),
+            size: payload.len(),
+        });
+        i = remainder;
+    }
+    Ok((i, ()))
+}
+
+/// Parses a single *dir* stream record without interpreting its payload.
+///
+/// Records follow the common `Id` (`u16`), `Size` (`u32`), `Data` layout, with the
+/// exception of the `PROJECTVERSION` record. Its `Reserved` field holds the fixed value
+/// 4, but the record carries 6 bytes of data.
+pub(crate) fn dir_record(i: &[u8]) -> IResult<&[u8], DirRecord<'_>, FormatError<&[u8]>> {
+    const PROJECTVERSION_ID: u16 = 0x0009;
+    let (i, id) = le_u16(i)?;
+    if id == PROJECTVERSION_ID {
+        let (i, _) = tag(U32_FIXED_SIZE_4)(i)?;
+        let (i, payload) = take(6_usize)(i)?;
+        Ok((i, DirRecord { id, payload }))
+    } else {
+        let (i, payload) = length_data_u32(i)?;
+        Ok((i, DirRecord { id, payload }))
+    }
+}
+
+fn parse_syskind(input: &[u8]) -> IResult<&[u8], SysKind, FormatError<&[u8]>> {
     const SYS_KIND_SIGNATURE: &[u8] = &[0x01, 0x00];
     let (i, sys_kind) = preceded(
         tuple((tag(SYS_KIND_SIGNATURE), tag(U32_FIXED_SIZE_4))),
         le_u32,
-    )(i)?;
+    )(input)?;
     match sys_kind {
         0x0000_0000 => Ok((i, SysKind::Win16)),
         0x0000_0001 => Ok((i, SysKind::Win32)),
         0x0000_0002 => Ok((i, SysKind::MacOs)),
         0x0000_0003 => Ok((i, SysKind::Win64)),
-        _ => Err(Error(FormatError::UnexpectedValue)),
+        _ => Err(Error(FormatError::UnexpectedValue(input))),
     }
 }
 
@@ -188,35 +905,69 @@ fn parse_lcid_invoke(i: &[u8]) -> IResult<&[u8], u32, FormatError<&[u8]>> {
     Ok((i, lcid_invoke))
 }
 
-fn parse_code_page(i: &[u8]) -> IResult<&[u8], u16, FormatError<&[u8]>> {
+/// The code page used to decode strings if the project's code page cannot be mapped to
+/// an encoding, and the fallback is enabled (Windows-1252).
+pub(crate) const FALLBACK_CODE_PAGE: u16 = 1252;
+
+/// Parses the `PROJECTCODEPAGE` record.
+///
+/// All MBCS strings in the *dir* stream are decoded using this code page. The code page
+/// is validated here, i.e. before any string is decoded, so that a code page that cannot
+/// be mapped to an encoding is reported at the offending record. If enabled, such a code
+/// page is replaced with `FALLBACK_CODE_PAGE` instead. A code page override replaces the
+/// declared code page unconditionally.
+fn parse_code_page<'a>(
+    input: &'a [u8],
+    state: &mut ParseState<'_>,
+) -> IResult<&'a [u8], u16, FormatError<&'a [u8]>> {
     const CODE_PAGE_SIGNATURE: &[u8] = &[0x03, 0x00];
     let (i, code_page) = preceded(
         tuple((tag(CODE_PAGE_SIGNATURE), tag(U32_FIXED_SIZE_2))),
         le_u16,
-    )(i)?;
+    )(input)?;
+    debug!("Code page {} declared", code_page);
+    if let Some(code_page) = state.code_page {
+        if to_encoding(code_page).is_none() {
+            return Err(Error(FormatError::Encoding(code_page)));
+        }
+        debug!("Code page overridden with {}", code_page);
+        return Ok((i, code_page));
+    }
+    if to_encoding(code_page).is_none() {
+        if !state.fallback_encoding {
+            return Err(Error(FormatError::Encoding(code_page)));
+        }
+        debug!("Code page falling back to {}", FALLBACK_CODE_PAGE);
+        state.warnings.push(Warning::EncodingFallback {
+            offset: state.offset(input),
+            code_page,
+            fallback: FALLBACK_CODE_PAGE,
+        });
+        return Ok((i, FALLBACK_CODE_PAGE));
+    }
     Ok((i, code_page))
 }
 
 fn parse_name(i: &[u8]) -> IResult<&[u8], Vec<u8>, FormatError<&[u8]>> {
     const NAME_SIGNATURE: &[u8] = &[0x04, 0x00];
-    let (i, name) = preceded(tag(NAME_SIGNATURE), length_data(le_u32))(i)?;
+    let (i, name) = preceded(tag(NAME_SIGNATURE), length_data_u32)(i)?;
     Ok((i, name.to_vec()))
 }
 
 fn parse_doc_string(i: &[u8]) -> IResult<&[u8], Vec<u8>, FormatError<&[u8]>> {
     const DOC_STRING_SIGNATURE: &[u8] = &[0x05, 0x00];
-    let (i, doc_string) = preceded(tag(DOC_STRING_SIGNATURE), length_data(le_u32))(i)?;
+    let (i, doc_string) = preceded(tag(DOC_STRING_SIGNATURE), length_data_u32)(i)?;
     Ok((i, doc_string.to_vec()))
 }
 
-fn parse_doc_string_unicode(i: &[u8]) -> IResult<&[u8], Vec<u8>, FormatError<&[u8]>> {
+fn parse_doc_string_unicode(input: &[u8]) -> IResult<&[u8], Vec<u8>, FormatError<&[u8]>> {
     const DOC_STRING_UNICODE_SIGNATURE: &[u8] = &[0x40, 0x00];
     let (i, doc_string_unicode) =
-        preceded(tag(DOC_STRING_UNICODE_SIGNATURE), length_data(le_u32))(i)?;
+        preceded(tag(DOC_STRING_UNICODE_SIGNATURE), length_data_u32)(input)?;
     // `doc_string_unicode` represents a sequence of UTF-16 code units. If its length is uneven,
     // the input is malformed.
     if (doc_string_unicode.len() & 1_usize) != 0 {
-        Err(Error(FormatError::UnexpectedValue))
+        Err(Error(FormatError::UnexpectedValue(input)))
     } else {
         Ok((i, doc_string_unicode.to_vec()))
     }
@@ -224,13 +975,13 @@ fn parse_doc_string_unicode(i: &[u8]) -> IResult<&[u8], Vec<u8>, FormatError<&[u
 
 fn parse_help_file_1(i: &[u8]) -> IResult<&[u8], Vec<u8>, FormatError<&[u8]>> {
     const HELP_FILE_1_SIGNATURE: &[u8] = &[0x06, 0x00];
-    let (i, help_file_1) = preceded(tag(HELP_FILE_1_SIGNATURE), length_data(le_u32))(i)?;
+    let (i, help_file_1) = preceded(tag(HELP_FILE_1_SIGNATURE), length_data_u32)(i)?;
     Ok((i, help_file_1.to_vec()))
 }
 
 fn parse_help_file_2(i: &[u8]) -> IResult<&[u8], Vec<u8>, FormatError<&[u8]>> {
     const HELP_FILE_2_SIGNATURE: &[u8] = &[0x3d, 0x00];
-    let (i, help_file_2) = preceded(tag(HELP_FILE_2_SIGNATURE), length_data(le_u32))(i)?;
+    let (i, help_file_2) = preceded(tag(HELP_FILE_2_SIGNATURE), length_data_u32)(i)?;
     Ok((i, help_file_2.to_vec()))
 }
 
@@ -263,17 +1014,15 @@ fn parse_version(i: &[u8]) -> IResult<&[u8], (u32, u16), FormatError<&[u8]>> {
 
 fn parse_constants(i: &[u8]) -> IResult<&[u8], Option<Vec<u8>>, FormatError<&[u8]>> {
     const CONSTANTS_SIGNATURE: &[u8] = &[0x0c, 0x00];
-    let (i, constants) = opt(preceded(tag(CONSTANTS_SIGNATURE), length_data(le_u32)))(i)?;
+    let (i, constants) = opt(preceded(tag(CONSTANTS_SIGNATURE), length_data_u32))(i)?;
     let constants = constants.map(|slice| slice.to_vec());
     Ok((i, constants))
 }
 
 fn parse_constants_unicode(i: &[u8]) -> IResult<&[u8], Option<Vec<u8>>, FormatError<&[u8]>> {
     const CONSTANTS_UNICODE_SIGNATURE: &[u8] = &[0x3c, 0x00];
-    let (i, constants_unicode) = opt(preceded(
-        tag(CONSTANTS_UNICODE_SIGNATURE),
-        length_data(le_u32),
-    ))(i)?;
+    let (i, constants_unicode) =
+        opt(preceded(tag(CONSTANTS_UNICODE_SIGNATURE), length_data_u32))(i)?;
     let constants_unicode = constants_unicode.map(|slice| slice.to_vec());
     Ok((i, constants_unicode))
 }
@@ -281,21 +1030,29 @@ fn parse_constants_unicode(i: &[u8]) -> IResult<&[u8], Option<Vec<u8>>, FormatEr
 // -------------------------------------------------------------------------
 // -------------------------------------------------------------------------
 
-#[allow(clippy::type_complexity)]
-fn parse_reference_name(
-    i: &[u8],
+fn parse_reference_name<'a>(
+    i: &'a [u8],
     code_page: u16,
-) -> IResult<&[u8], Option<String>, FormatError<&[u8]>> {
+    state: &mut ParseState<'_>,
+) -> IResult<&'a [u8], Option<String>, FormatError<&'a [u8]>> {
     const NAME_SIGNATURE: &[u8] = &[0x16, 0x00];
     const NAME_UNICODE_SIGNATURE: &[u8] = &[0x3e, 0x00];
-    let (i, name) = opt(tuple((
-        preceded(tag(NAME_SIGNATURE), length_data(le_u32)),
-        preceded(tag(NAME_UNICODE_SIGNATURE), length_data(le_u32)),
-    )))(i)?;
-    // name_unicode MUST contain the UTF-16 encoding of name. Can be dropped without
-    // loss of information.
-    if let Some((name, _name_unicode)) = name {
-        let name = cp_to_string(name, code_page);
+    let (i, name) = opt(preceded(tag(NAME_SIGNATURE), length_data_u32))(i)?;
+    if let Some(name) = name {
+        // name_unicode MUST contain the UTF-16 encoding of name. Can be dropped without
+        // loss of information.
+        // The specification mandates this record, though some files in the wild omit it.
+        // It is thus treated as optional.
+        let record = i;
+        let (i, name_unicode) = opt(preceded(tag(NAME_UNICODE_SIGNATURE), length_data_u32))(i)?;
+        trace!(
+            "REFERENCENAME Unicode record present: {}",
+            name_unicode.is_some()
+        );
+        let name = decode(name, code_page)?;
+        if let Some(name_unicode) = name_unicode {
+            state.verify_duplicate(record, || is_utf16_of(name_unicode, &name));
+        }
         Ok((i, Some(name)))
     } else {
         Ok((i, None))
@@ -307,15 +1064,16 @@ fn parse_reference_original(
     code_page: u16,
 ) -> IResult<&[u8], String, FormatError<&[u8]>> {
     const ORIGINAL_SIGNATURE: &[u8] = &[0x33, 0x00];
-    let (i, libid_original) = preceded(tag(ORIGINAL_SIGNATURE), length_data(le_u32))(i)?;
-    let libid_original = cp_to_string(libid_original, code_page);
+    let (i, libid_original) = preceded(tag(ORIGINAL_SIGNATURE), length_data_u32)(i)?;
+    let libid_original = decode(libid_original, code_page)?;
     Ok((i, libid_original))
 }
 
-fn parse_reference_control(
-    i: &[u8],
+fn parse_reference_control<'a>(
+    i: &'a [u8],
     code_page: u16,
-) -> IResult<&[u8], ReferenceControl, FormatError<&[u8]>> {
+    state: &mut ParseState<'_>,
+) -> IResult<&'a [u8], ReferenceControl, FormatError<&'a [u8]>> {
     // REFERENCEORIGINAL Record is optional here
     let (_, id) = le_u16(i)?;
     let (i, libid_original) = match id {
@@ -328,25 +1086,47 @@ fn parse_reference_control(
 
     const CONTROL_SIGNATURE: &[u8] = &[0x2f, 0x00];
     let (i, libid_twiddled) =
-        preceded(tuple((tag(CONTROL_SIGNATURE), le_u32)), length_data(le_u32))(i)?;
-    let libid_twiddled = cp_to_string(libid_twiddled, code_page);
+        preceded(tuple((tag(CONTROL_SIGNATURE), le_u32)), length_data_u32)(i)?;
+    let libid_twiddled = decode(libid_twiddled, code_page)?;
 
     const RESERVED_1: &[u8] = &[0x00, 0x00, 0x00, 0x00];
     const RESERVED_2: &[u8] = &[0x00, 0x00];
     let (i, _) = tuple((tag(RESERVED_1), tag(RESERVED_2)))(i)?;
 
-    let (i, name_extended) = parse_reference_name(i, code_page)?;
-
+    // The extended type library (an optional NameRecordExtended record, followed by the
+    // Reserved3 block) is mandated by the specification, though some files in the wild
+    // omit it. A REFERENCENAME record that isn't followed by the Reserved3 block belongs
+    // to the next REFERENCE.
     const RESERVED_3: &[u8] = &[0x30, 0x00];
-    let (i, libid_extended) = preceded(tuple((tag(RESERVED_3), le_u32)), length_data(le_u32))(i)?;
-    let libid_extended = cp_to_string(libid_extended, code_page);
+    let (remainder, name_extended) = parse_reference_name(i, code_page, state)?;
+    if !remainder.starts_with(RESERVED_3) {
+        trace!("REFERENCECONTROL extended type library absent");
+        return Ok((
+            i,
+            ReferenceControl {
+                name: None,
+                libid_original,
+                libid_twiddled,
+                name_extended: None,
+                libid_extended: String::new(),
+                guid: [0; 16],
+                cookie: 0,
+            },
+        ));
+    }
+    let i = remainder;
+
+    let (i, libid_extended) = preceded(tuple((tag(RESERVED_3), le_u32)), length_data_u32)(i)?;
+    let libid_extended = decode(libid_extended, code_page)?;
 
     const RESERVED_4: &[u8] = &[0x00, 0x00, 0x00, 0x00];
     const RESERVED_5: &[u8] = &[0x00, 0x00];
     let (i, _) = tuple((tag(RESERVED_4), tag(RESERVED_5)))(i)?;
 
     let (i, guid) = take(16_usize)(i)?;
-    let guid = guid.to_vec();
+    let guid = guid
+        .try_into()
+        .map_err(|_| Error(FormatError::UnexpectedValue(guid)))?;
 
     let (i, cookie) = le_u32(i)?;
 
@@ -369,11 +1149,8 @@ fn parse_reference_registered(
     code_page: u16,
 ) -> IResult<&[u8], ReferenceRegistered, FormatError<&[u8]>> {
     const REGISTERED_SIGNATURE: &[u8] = &[0x0d, 0x00];
-    let (i, libid) = preceded(
-        tuple((tag(REGISTERED_SIGNATURE), le_u32)),
-        length_data(le_u32),
-    )(i)?;
-    let libid = cp_to_string(libid, code_page);
+    let (i, libid) = preceded(tuple((tag(REGISTERED_SIGNATURE), le_u32)), length_data_u32)(i)?;
+    let libid = decode(libid, code_page)?;
 
     const RESERVED_1: &[u8] = &[0x00, 0x00, 0x00, 0x00];
     const RESERVED_2: &[u8] = &[0x00, 0x00];
@@ -387,13 +1164,13 @@ fn parse_reference_project(
     code_page: u16,
 ) -> IResult<&[u8], ReferenceProject, FormatError<&[u8]>> {
     let (i, (libid_absolute, libid_relative, major_version, minor_version)) = tuple((
-        preceded(tuple((tag(&[0x0e, 0x00]), le_u32)), length_data(le_u32)),
-        length_data(le_u32),
+        preceded(tuple((tag(&[0x0e, 0x00]), le_u32)), length_data_u32),
+        length_data_u32,
         le_u32,
         le_u16,
     ))(i)?;
-    let libid_absolute = cp_to_string(libid_absolute, code_page);
-    let libid_relative = cp_to_string(libid_relative, code_page);
+    let libid_absolute = decode(libid_absolute, code_page)?;
+    let libid_relative = decode(libid_relative, code_page)?;
 
     Ok((
         i,
@@ -416,50 +1193,83 @@ fn parse_reference_project(
 ///
 /// Returns `Some(reference)` if a variant was found, `None` if the end of the array was
 /// reached, or an error.
-fn parse_reference(
-    i: &[u8],
+fn parse_reference<'a>(
+    i: &'a [u8],
     code_page: u16,
-) -> IResult<&[u8], Option<Reference>, FormatError<&[u8]>> {
-    let (i, name) = parse_reference_name(i, code_page)?;
+    state: &mut ParseState<'_>,
+) -> IResult<&'a [u8], Option<Reference>, FormatError<&'a [u8]>> {
+    let (i, _) = skip_unknown_records(i, state)?;
+    let (i, name) = parse_reference_name(i, code_page, state)?;
+    let (i, _) = skip_unknown_records(i, state)?;
     // Determine REFERENCE Record variant (or end of array)
     let (_, id) = le_u16(i)?;
     match id {
         0x002f_u16 => {
-            let (i, mut value) = parse_reference_control(i, code_page)?;
+            debug!("REFERENCECONTROL at offset 0x{:X}", state.offset(i));
+            let (i, mut value) = parse_reference_control(i, code_page, state)?;
             value.name = name;
             Ok((i, Some(Reference::Control(value))))
         }
         0x0033_u16 => {
-            let (i, libid_original) = parse_reference_original(i, code_page)?;
+            let (remainder, libid_original) = parse_reference_original(i, code_page)?;
+            // A REFERENCEORIGINAL record is commonly followed by the REFERENCECONTROL
+            // record it belongs to. The name applies to the control reference in that
+            // case.
+            if let Ok((_, 0x002f_u16)) = le_u16::<_, FormatError<&[u8]>>(remainder) {
+                debug!(
+                    "REFERENCECONTROL (with REFERENCEORIGINAL) at offset 0x{:X}",
+                    state.offset(i)
+                );
+                let (i, mut value) = parse_reference_control(i, code_page, state)?;
+                value.name = name;
+                return Ok((i, Some(Reference::Control(value))));
+            }
+            debug!("REFERENCEORIGINAL at offset 0x{:X}", state.offset(i));
             let original = ReferenceOriginal {
                 name,
                 libid_original,
             };
-            Ok((i, Some(Reference::Original(original))))
+            Ok((remainder, Some(Reference::Original(original))))
         }
         0x000d_u16 => {
+            debug!("REFERENCEREGISTERED at offset 0x{:X}", state.offset(i));
             let (i, mut value) = parse_reference_registered(i, code_page)?;
             value.name = name;
             Ok((i, Some(Reference::Registered(value))))
         }
         0x000e_u16 => {
+            debug!("REFERENCEPROJECT at offset 0x{:X}", state.offset(i));
             let (i, mut value) = parse_reference_project(i, code_page)?;
             value.name = name;
             Ok((i, Some(Reference::Project(value))))
         }
         0x000f_u16 => Ok((i, None)),
-        _ => Err(Error(FormatError::UnexpectedValue)),
+        _ => Err(Error(FormatError::UnexpectedValue(i))),
     }
 }
 
-fn parse_references(
-    i: &[u8],
+fn parse_references<'a>(
+    i: &'a [u8],
     code_page: u16,
-) -> IResult<&[u8], Vec<Reference>, FormatError<&[u8]>> {
+    state: &mut ParseState<'_>,
+) -> IResult<&'a [u8], Vec<Reference>, FormatError<&'a [u8]>> {
     let mut result = Vec::new();
     let mut i = i;
     loop {
-        let (remainder, value) = parse_reference(i, code_page)?;
+        let (remainder, value) = match parse_reference(i, code_page, state) {
+            Ok(value) => value,
+            Err(e) if state.lenient => match skip_reference(i) {
+                Some(remainder) => {
+                    state.warnings.push(Warning::SkippedReference {
+                        offset: state.offset(i),
+                    });
+                    i = remainder;
+                    continue;
+                }
+                None => return Err(e),
+            },
+            Err(e) => return Err(e),
+        };
         i = remainder;
         if let Some(reference) = value {
             result.push(reference);
@@ -469,62 +1279,140 @@ fn parse_references(
     }
 }
 
+/// Skips the records of a malformed REFERENCE, up to the start of the next REFERENCE or
+/// the PROJECTMODULES record.
+///
+/// Returns `None` if the records cannot be delimited.
+fn skip_reference(i: &[u8]) -> Option<&[u8]> {
+    let mut i = i;
+    let mut previous = None;
+    loop {
+        let (_, id) = le_u16::<_, FormatError<&[u8]>>(i).ok()?;
+        if let Some(previous) = previous {
+            // A REFERENCE starts with an optional REFERENCENAME record. A
+            // REFERENCEORIGINAL record can be followed by a REFERENCECONTROL record, and
+            // the REFERENCECONTROL record by a REFERENCENAME record, that belong to the
+            // same REFERENCE.
+            let after_variant = !matches!(previous, 0x0016 | 0x003e);
+            let boundary = match id {
+                0x000f => true,
+                0x0016 => after_variant && previous != 0x002f,
+                0x002f => after_variant && previous != 0x0033,
+                0x000d | 0x000e | 0x0033 => after_variant,
+                _ => false,
+            };
+            if boundary {
+                return Some(i);
+            }
+        }
+        let (remainder, record) = dir_record(i).ok()?;
+        previous = Some(record.id);
+        i = remainder;
+    }
+}
+
 // -------------------------------------------------------------------------
 // -------------------------------------------------------------------------
 
-fn parse_module(i: &[u8], code_page: u16) -> IResult<&[u8], Module, FormatError<&[u8]>> {
+fn parse_module<'a>(
+    i: &'a [u8],
+    code_page: u16,
+    state: &mut ParseState<'_>,
+) -> IResult<&'a [u8], Module, FormatError<&'a [u8]>> {
     // MODULENAME Record
-    let (i, name) = preceded(tag(&[0x19, 0x00]), length_data(le_u32))(i)?;
-    let name = cp_to_string(name, code_page);
+    let (i, name) = preceded(tag(&[0x19, 0x00]), length_data_u32)(i)?;
+    let name = decode(name, code_page)?;
+
+    let (i, _) = skip_unknown_records(i, state)?;
 
     // (Optional) MODULENAMEUNICODE Record
     // If present it MUST be the UTF-16 encoding of MODULENAME. It can safely be dropped.
-    let (i, _name_unicode) = opt(preceded(tag(&[0x47, 0x00]), length_data(le_u32)))(i)?;
+    let record = i;
+    let (i, name_unicode) = opt(preceded(tag(&[0x47, 0x00]), length_data_u32))(i)?;
+    if let Some(name_unicode) = name_unicode {
+        state.verify_duplicate(record, || is_utf16_of(name_unicode, &name));
+    }
+
+    let (i, _) = skip_unknown_records(i, state)?;
 
     // MODULESTREAMNAME Record
     // stream_name_unicode MUST be the UTF-16 encoding of stream_name. It can safely be dropped.
-    let (i, (stream_name, _stream_name_unicode)) = tuple((
-        preceded(tag(&[0x1a, 0x00]), length_data(le_u32)),
-        preceded(tag(&[0x32, 0x00]), length_data(le_u32)),
-    ))(i)?;
-    let stream_name = cp_to_string(stream_name, code_page);
+    let (i, stream_name) = preceded(tag(&[0x1a, 0x00]), length_data_u32)(i)?;
+    let record = i;
+    let (i, stream_name_unicode) = preceded(tag(&[0x32, 0x00]), length_data_u32)(i)?;
+    let stream_name = decode(stream_name, code_page)?;
+    state.verify_duplicate(record, || is_utf16_of(stream_name_unicode, &stream_name));
+
+    let (i, _) = skip_unknown_records(i, state)?;
 
     // MODULEDOCSTRING Record
-    // doc_string_unicode MUST be the UTF-16 encoding of doc_string. It can safely be dropped.
-    let (i, (doc_string, _doc_string_unicode)) = tuple((
-        preceded(tag(&[0x1c, 0x00]), length_data(le_u32)),
-        preceded(tag(&[0x48, 0x00]), length_data(le_u32)),
-    ))(i)?;
-    let doc_string = cp_to_string(doc_string, code_page);
+    // doc_string_unicode MUST be the UTF-16 encoding of doc_string. Some producers leave the
+    // MBCS string empty when the description cannot be represented in the project's code
+    // page, so the Unicode string is used in that case.
+    let (i, doc_string) = preceded(tag(&[0x1c, 0x00]), length_data_u32)(i)?;
+    let record = i;
+    let (i, doc_string_unicode) = preceded(tag(&[0x48, 0x00]), length_data_u32)(i)?;
+    let doc_string = if doc_string.is_empty() && !doc_string_unicode.is_empty() {
+        utf16_to_string(doc_string_unicode)
+    } else {
+        let doc_string = decode(doc_string, code_page)?;
+        state.verify_duplicate(record, || is_utf16_of(doc_string_unicode, &doc_string));
+        doc_string
+    };
+
+    let (i, _) = skip_unknown_records(i, state)?;
 
     // MODULEOFFSET Record
     let (i, text_offset) = preceded(tuple((tag(&[0x31, 0x00]), tag(U32_FIXED_SIZE_4))), le_u32)(i)?;
     let text_offset = text_offset as _;
 
+    let (i, _) = skip_unknown_records(i, state)?;
+
     // MODULEHELPCONTEXT Record
     let (i, help_context) =
         preceded(tuple((tag(&[0x1e, 0x00]), tag(U32_FIXED_SIZE_4))), le_u32)(i)?;
 
+    let (i, _) = skip_unknown_records(i, state)?;
+
     // MODULECOOKIE Record
-    // Cookie MUST be ignored on read.
-    let (i, _cookie) = preceded(tuple((tag(&[0x2c, 0x00]), tag(U32_FIXED_SIZE_2))), le_u16)(i)?;
+    // Cookie MUST be ignored on read. It is retained for informational purposes.
+    let (i, cookie) = preceded(tuple((tag(&[0x2c, 0x00]), tag(U32_FIXED_SIZE_2))), le_u16)(i)?;
+
+    let (i, _) = skip_unknown_records(i, state)?;
 
     // MODULETYPE Record
     let (i, id) = le_u16(i)?;
     let module_type = match id {
         0x0021_u16 => ModuleType::Procedural,
         0x0022_u16 => ModuleType::DocClsDesigner,
-        _ => return Err(Error(FormatError::UnexpectedValue)),
+        _ => return Err(Error(FormatError::UnexpectedValue(i))),
     };
     let (i, _) = tag(&[0x00, 0x00, 0x00, 0x00])(i)?;
 
-    // MODULEREADONLY Record
-    let (i, read_only) = opt(tag(&[0x25, 0x00, 0x00, 0x00, 0x00, 0x00]))(i)?;
-    let read_only = read_only.is_some();
-
-    // MODULEPRIVATE Record
-    let (i, private) = opt(tag(&[0x28, 0x00, 0x00, 0x00, 0x00, 0x00]))(i)?;
-    let private = private.is_some();
+    // (Optional) MODULEREADONLY and MODULEPRIVATE Records
+    // The specification orders MODULEREADONLY before MODULEPRIVATE, but the VBE accepts
+    // either order. Each record may appear at most once.
+    const READ_ONLY: &[u8] = &[0x25, 0x00, 0x00, 0x00, 0x00, 0x00];
+    const PRIVATE: &[u8] = &[0x28, 0x00, 0x00, 0x00, 0x00, 0x00];
+    let mut i = i;
+    let mut read_only = false;
+    let mut private = false;
+    loop {
+        let (remainder, _) = skip_unknown_records(i, state)?;
+        let flag = if remainder.starts_with(READ_ONLY) {
+            &mut read_only
+        } else if remainder.starts_with(PRIVATE) {
+            &mut private
+        } else {
+            i = remainder;
+            break;
+        };
+        if *flag {
+            return Err(Error(FormatError::UnexpectedValue(remainder)));
+        }
+        *flag = true;
+        i = &remainder[READ_ONLY.len()..];
+    }
 
     // Terminator
     let (i, _) = tag(&[0x2b, 0x00])(i)?;
@@ -543,19 +1431,31 @@ fn parse_module(i: &[u8], code_page: u16) -> IResult<&[u8], Module, FormatError<
             module_type,
             read_only,
             private,
+            cookie,
         },
     ))
 }
 
-fn parse_modules(i: &[u8], code_page: u16) -> IResult<&[u8], Vec<Module>, FormatError<&[u8]>> {
+fn parse_modules<'a>(
+    i: &'a [u8],
+    code_page: u16,
+    state: &mut ParseState<'_>,
+) -> IResult<&'a [u8], Vec<Module>, FormatError<&'a [u8]>> {
     let (i, count) = preceded(tuple((tag(&[0x0f, 0x00]), tag(U32_FIXED_SIZE_2))), le_u16)(i)?;
+    let (i, _) = skip_unknown_records(i, state)?;
     // Cookie MUST be ignored on read.
     let (i, _cookie) = preceded(tuple((tag(&[0x13, 0x00]), tag(U32_FIXED_SIZE_2))), le_u16)(i)?;
 
     let mut modules = Vec::new();
     let mut i = i;
     for _ in 0..count {
-        let (remainder, module) = parse_module(i, code_page)?;
+        let (remainder, _) = skip_unknown_records(i, state)?;
+        let (remainder, module) = parse_module(remainder, code_page, state)?;
+        debug!(
+            "Module {:?} in stream {:?}, text offset 0x{:X}",
+            module.name, module.stream_name, module.text_offset
+        );
+        state.emit(ParseEvent::ModuleParsed { name: &module.name });
         i = remainder;
         modules.push(module);
     }
@@ -566,34 +1466,62 @@ fn parse_modules(i: &[u8], code_page: u16) -> IResult<&[u8], Vec<Module>, Format
 // -------------------------------------------------------------------------
 // -------------------------------------------------------------------------
 
-/// *dir* stream parser.
+/// *dir* stream parser, using default options.
+#[cfg(test)]
 pub(crate) fn parse_project_information(
     i: &[u8],
-) -> IResult<&[u8], ProjectInformation, FormatError<&[u8]>> {
+) -> IResult<&[u8], ProjectMetadata, FormatError<&[u8]>> {
+    let mut state = ParseState::new(&OpenOptions::new(), i);
+    parse_project_information_with(i, &mut state)
+}
+
+/// *dir* stream parser, configured through `state`.
+pub(crate) fn parse_project_information_with<'a>(
+    i: &'a [u8],
+    state: &mut ParseState<'_>,
+) -> IResult<&'a [u8], ProjectMetadata, FormatError<&'a [u8]>> {
     let (i, sys_kind) = parse_syskind(i)?;
+    let (i, _) = skip_unknown_records(i, state)?;
     let (i, compat) = parse_compat(i)?;
+    trace!("PROJECTCOMPATVERSION record present: {}", compat.is_some());
+    let (i, _) = skip_unknown_records(i, state)?;
     let (i, lcid) = parse_lcid(i)?;
+    let (i, _) = skip_unknown_records(i, state)?;
     let (i, lcid_invoke) = parse_lcid_invoke(i)?;
-    let (i, code_page) = parse_code_page(i)?;
+    let (i, _) = skip_unknown_records(i, state)?;
+    // The code page MUST be known before decoding any MBCS string. The record order is
+    // fixed; any string record preceding `PROJECTCODEPAGE` is rejected here.
+    let (i, code_page) = parse_code_page(i, state)?;
+    let (i, _) = skip_unknown_records(i, state)?;
 
     let (i, name) = parse_name(i)?;
-    let name = cp_to_string(&name, code_page);
+    let name = decode(&name, code_page)?;
+    let (i, _) = skip_unknown_records(i, state)?;
 
     let (i, doc_string) = parse_doc_string(i)?;
-    let doc_string = cp_to_string(&doc_string, code_page);
+    let doc_string = decode(&doc_string, code_page)?;
 
     // doc_string_unicode MUST contain the UTF-16 encoding of doc_string. Can safely be dropped.
-    let (i, _doc_string_unicode) = parse_doc_string_unicode(i)?;
+    let record = i;
+    let (i, doc_string_unicode) = parse_doc_string_unicode(i)?;
+    state.verify_duplicate(record, || is_utf16_of(&doc_string_unicode, &doc_string));
+    let (i, _) = skip_unknown_records(i, state)?;
 
     let (i, help_file_1) = parse_help_file_1(i)?;
-    let help_file_1 = cp_to_string(&help_file_1, code_page);
 
     // help_file_2 MUST contain the same bytes as help_file_1. Can safely be dropped.
-    let (i, _help_file_2) = parse_help_file_2(i)?;
+    let record = i;
+    let (i, help_file_2) = parse_help_file_2(i)?;
+    state.verify_duplicate(record, || help_file_2 == help_file_1);
+    let help_file_1 = decode(&help_file_1, code_page)?;
+    let (i, _) = skip_unknown_records(i, state)?;
 
     let (i, help_context) = parse_help_context(i)?;
+    let (i, _) = skip_unknown_records(i, state)?;
     let (i, lib_flags) = parse_lib_flags(i)?;
+    let (i, _) = skip_unknown_records(i, state)?;
     let (i, (version_major, version_minor)) = parse_version(i)?;
+    let (i, _) = skip_unknown_records(i, state)?;
 
     // The `PROJECTCONSTANTS` record is optional (as a whole); make sure to only parse the
     // Unicode portion if `parse_constants` returned `Some`.
@@ -601,32 +1529,69 @@ pub(crate) fn parse_project_information(
     // TODO: Consider consolidating CP and Unicode parsing into a single function. This
     // would avoid having to subsequently deal with the outcome of this function.
     let (i, constants) = parse_constants(i)?;
-    let constants = constants.map(|constants| cp_to_string(&constants, code_page));
+    trace!("PROJECTCONSTANTS record present: {}", constants.is_some());
+    let constants = constants
+        .map(|constants| decode(&constants, code_page))
+        .transpose()?;
 
-    let i = if constants.is_some() {
+    let i = if let Some(constants) = &constants {
         // constants_unicode MUST contain the UTF-16 encoding of constants. Can safely be
         // dropped.
-        let (i, _constants_unicode) = parse_constants_unicode(i)?;
+        let record = i;
+        let (i, constants_unicode) = parse_constants_unicode(i)?;
+        trace!(
+            "PROJECTCONSTANTS Unicode record present: {}",
+            constants_unicode.is_some()
+        );
+        if let Some(constants_unicode) = constants_unicode {
+            state.verify_duplicate(record, || is_utf16_of(&constants_unicode, constants));
+        }
         i
     } else {
         i
     };
 
-    let (i, references) = parse_references(i, code_page)?;
-
-    let (i, modules) = parse_modules(i, code_page)?;
-
-    // Terminator
-    let (i, _) = tag(&[0x10, 0x00])(i)?;
-
-    // Reserved
-    let (i, _) = tag(&[0x00, 0x00, 0x00, 0x00])(i)?;
+    let (i, references) = parse_references(i, code_page, state)?;
+    state.emit(ParseEvent::ReferencesParsed {
+        count: references.len(),
+    });
+
+    let (i, modules) = parse_modules(i, code_page, state)?;
+    let (i, _) = skip_unknown_records(i, state)?;
+
+    // Terminator, followed by 4 reserved bytes. In lenient mode, a missing terminator is
+    // tolerated, and any trailing data is ignored.
+    let terminator: IResult<_, _, FormatError<&[u8]>> =
+        tuple((tag(&[0x10, 0x00]), tag(&[0x00, 0x00, 0x00, 0x00])))(i);
+    let i = match terminator {
+        Ok((i, _)) => i,
+        Err(_) if state.lenient => {
+            state.warnings.push(Warning::MissingTerminator {
+                offset: state.offset(i),
+            });
+            i
+        }
+        Err(e) => return Err(e),
+    };
 
-    debug_assert_eq!(i.len(), 0, "Input not fully read");
+    // In lenient mode, trailing data is reported as a warning, and consumed.
+    let i = if i.is_empty() {
+        i
+    } else {
+        if state.lenient {
+            state.warnings.push(Warning::TrailingData {
+                offset: state.offset(i),
+                size: i.len(),
+            });
+            &i[i.len()..]
+        } else {
+            return Err(Error(FormatError::TrailingData(i)));
+        }
+    };
 
     Ok((
         i,
-        ProjectInformation {
+        ProjectMetadata {
             information: Information {
                 sys_kind,
                 compat,
@@ -648,47 +1613,106 @@ pub(crate) fn parse_project_information(
     ))
 }
 
+/// *_VBA_PROJECT* stream parser.
+///
+/// Returns the VBA version. The remainder is the performance cache.
+#[cfg(feature = "std")]
+pub(crate) fn parse_vba_project(i: &[u8]) -> IResult<&[u8], u16, FormatError<&[u8]>> {
+    const RESERVED1: &[u8] = &[0xcc, 0x61];
+    let (i, _) = tag(RESERVED1)(i)?;
+    let (i, version) = le_u16(i)?;
+    // Reserved2 (1 byte) and Reserved3 (2 bytes)
+    let (i, _) = take(3_usize)(i)?;
+    Ok((i, version))
+}
+
+/// Pairs of MBCS and Unicode module names, as stored in the *PROJECTwm* stream.
+#[cfg(feature = "std")]
+pub(crate) type NameMap = Vec<(String, String)>;
+
+/// *PROJECTwm* stream parser.
+///
+/// Returns the pairs of MBCS and Unicode module names, decoding the MBCS names using
+/// `code_page`. Data following the terminator is returned as the remainder.
+#[cfg(feature = "std")]
+pub(crate) fn parse_name_map(
+    i: &[u8],
+    code_page: u16,
+) -> IResult<&[u8], NameMap, FormatError<&[u8]>> {
+    let mut i = i;
+    let mut name_map = Vec::new();
+    // Module names cannot be empty, so two NUL bytes in place of the next MBCS name
+    // terminate the map.
+    while !i.starts_with(&[0x00, 0x00]) {
+        let (remainder, name) = nul_terminated(i, 1)?;
+        let (remainder, name_unicode) = nul_terminated(remainder, 2)?;
+        name_map.push((decode(name, code_page)?, utf16_to_string(name_unicode)));
+        i = remainder;
+    }
+    Ok((&i[2..], name_map))
+}
+
+/// Splits off a string terminated by a NUL character of `char_size` bytes. The
+/// terminator is consumed, but not included in the result.
+#[cfg(feature = "std")]
+fn nul_terminated(i: &[u8], char_size: usize) -> IResult<&[u8], &[u8], FormatError<&[u8]>> {
+    let len = i
+        .chunks_exact(char_size)
+        .position(|c| c.iter().all(|&b| b == 0x00))
+        .ok_or(Error(FormatError::Nom(i, ErrorKind::Eof)))?
+        * char_size;
+    Ok((&i[len + char_size..], &i[..len]))
+}
+
 // -------------------------------------------------------------------------
 // -------------------------------------------------------------------------
 
-/// # Panics
-///
-/// This function panics, if:
-/// * the passed in code page cannot be mapped to an encoding.
-/// * the maximum length of the output would overflow a `usize`.
-/// * part of the input could not be decoded into the allocated output `String`.
-///
-/// This is a temporary solution that allows me to postpone implementing error reporting
-/// to a later time, when the set of expected errors and the overall error handling strategy
-/// are better understood.
-pub(crate) fn cp_to_string(data: &[u8], code_page: u16) -> String {
-    let encoding = to_encoding(code_page).expect("Failed to map code page to an encoding.");
-    let mut decoder = encoding.new_decoder_without_bom_handling();
-    // The following returns `None` on overflow. That case is only expected with malformed document
-    // input, so let's just panic in this case.
-    let max_length = decoder.max_utf8_buffer_length(data.len()).unwrap();
-    let mut result = String::with_capacity(max_length);
-    let (decoder_result, _, _) = decoder.decode_to_string(data, &mut result, true);
-    assert_eq!(
-        decoder_result,
-        CoderResult::InputEmpty,
-        "Failed to decode full MBCS sequence."
-    );
+/// Decodes `data` using the encoding that corresponds to `code_page`.
+///
+/// Malformed byte sequences, including truncated MBCS sequences and bytes at undefined
+/// positions of single-byte code pages, are replaced with U+FFFD REPLACEMENT CHARACTER.
+///
+/// Returns an [`Error::Encoding`](crate::Error::Encoding) error if the code page cannot
+/// be mapped to an encoding.
+pub(crate) fn cp_to_string(data: &[u8], code_page: u16) -> crate::Result<String> {
+    cp_to_str(data, code_page).map(Cow::into_owned)
+}
 
-    result
+/// Same as [`cp_to_string`], borrowing from `data` where possible.
+///
+/// No allocation is made if the encoding is ASCII-compatible, and `data` is pure ASCII
+/// (or valid UTF-8, if the code page is 65001).
+pub(crate) fn cp_to_str(data: &[u8], code_page: u16) -> crate::Result<Cow<'_, str>> {
+    let encoding = to_encoding(code_page).ok_or(crate::Error::Encoding { code_page })?;
+    let (result, _had_errors) = encoding.decode_without_bom_handling(data);
+    Ok(result)
 }
 
-#[allow(dead_code)]
-fn utf16_to_string(data: &[u8]) -> String {
-    let mut decoder = UTF_16LE.new_decoder_without_bom_handling();
-    let max_length = decoder.max_utf8_buffer_length(data.len()).unwrap();
-    let mut result = String::with_capacity(max_length);
-    let (decoder_result, _, _) = decoder.decode_to_string(data, &mut result, true);
-    assert_eq!(
-        decoder_result,
-        CoderResult::InputEmpty,
-        "Failed to decode full UTF-16 sequence."
-    );
+/// Same as [`cp_to_string`], reusing the buffer of `data` if it doesn't need to be
+/// transcoded.
+#[cfg(feature = "std")]
+pub(crate) fn cp_into_string(data: Vec<u8>, code_page: u16) -> crate::Result<String> {
+    let encoding = to_encoding(code_page).ok_or(crate::Error::Encoding { code_page })?;
+    // ASCII-compatible encodings map ASCII onto itself.
+    let data = if encoding.is_ascii_compatible() && data.is_ascii() {
+        match String::from_utf8(data) {
+            Ok(result) => return Ok(result),
+            Err(e) => e.into_bytes(),
+        }
+    } else {
+        data
+    };
+    cp_to_string(&data, code_page)
+}
 
-    result
+/// Parser adapter for [`cp_to_string`].
+fn decode<'a>(data: &[u8], code_page: u16) -> Result<String, nom::Err<FormatError<&'a [u8]>>> {
+    cp_to_string(data, code_page).map_err(|_| Error(FormatError::Encoding(code_page)))
+}
+
+/// Decodes UTF-16LE encoded `data`, replacing malformed sequences with U+FFFD
+/// REPLACEMENT CHARACTER.
+fn utf16_to_string(data: &[u8]) -> String {
+    let (result, _had_errors) = UTF_16LE.decode_without_bom_handling(data);
+    result.into_owned()
 }
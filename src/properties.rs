@@ -0,0 +1,208 @@
+#![forbid(unsafe_code)]
+
+use crate::{Error, HostExtender, ModuleKind, ParseErrorKind, ProjectProperties, Result};
+
+/// Parses the decoded contents of a *PROJECT* stream.
+///
+/// The stream consists of `Key=Value` lines, separated into sections by `[Section]`
+/// lines. The properties preceding the first section describe the project, followed by
+/// the `[Host Extender Info]` and `[Workspace]` sections.
+///
+/// Lines that don't follow the `Key=Value` layout, and unknown keys and sections, are
+/// ignored.
+pub(crate) fn parse_project_properties(text: &str) -> ProjectProperties {
+    let mut properties = ProjectProperties::default();
+    let mut section = None;
+
+    for line in text.lines().map(|line| line.trim_end_matches('\r')) {
+        if let Some(name) = line
+            .strip_prefix('[')
+            .and_then(|line| line.strip_suffix(']'))
+        {
+            section = Some(name);
+            continue;
+        }
+        let (key, value) = match line.split_once('=') {
+            Some(property) => property,
+            None => continue,
+        };
+
+        match section {
+            None => match key {
+                "ID" => properties.id = Some(unquote(value).to_owned()),
+                "Name" => properties.name = Some(unquote(value).to_owned()),
+                "HelpFile" => properties.help_file = Some(unquote(value).to_owned()),
+                "HelpContextID" => {
+                    properties.help_context_id = unquote(value).parse().ok();
+                }
+                "CMG" => properties.protection_state = Some(unquote(value).to_owned()),
+                "DPB" => properties.password = Some(unquote(value).to_owned()),
+                "GC" => properties.visibility_state = Some(unquote(value).to_owned()),
+                // `Document` modules carry an additional version suffix,
+                // e.g. `Document=ThisDocument/&H00000000`.
+                "Document" => {
+                    let name = value.split('/').next().unwrap_or_default();
+                    properties.push_module(name, ModuleKind::Document);
+                }
+                "Module" => properties.push_module(value, ModuleKind::Procedural),
+                "Class" => properties.push_module(value, ModuleKind::Class),
+                "BaseClass" => properties.push_module(value, ModuleKind::Designer),
+                _ => {}
+            },
+            Some("Host Extender Info") => {
+                if let Some(host_extender) = parse_host_extender(key, value) {
+                    properties.host_extenders.push(host_extender);
+                }
+            }
+            Some(_) => {}
+        }
+    }
+
+    properties
+}
+
+/// Replaces the module name `old` with `new` in the decoded contents of a *PROJECT*
+/// stream.
+///
+/// This updates the `Module`, `Class`, `BaseClass`, and `Document` properties, and the
+/// module's line in the `[Workspace]` section. Names are compared case-insensitively.
+/// All other lines, and line terminators, are preserved.
+pub(crate) fn rename_module(text: &str, old: &str, new: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut section = None;
+
+    for line in text.split_inclusive('\n') {
+        let content = line.trim_end_matches(['\r', '\n']);
+        let terminator = &line[content.len()..];
+        if let Some(name) = content
+            .strip_prefix('[')
+            .and_then(|line| line.strip_suffix(']'))
+        {
+            section = Some(name);
+        }
+        let renamed = match (section, content.split_once('=')) {
+            (None, Some((key @ ("Module" | "Class" | "BaseClass"), value)))
+                if value.eq_ignore_ascii_case(old) =>
+            {
+                Some(format!("{}={}", key, new))
+            }
+            (None, Some(("Document", value))) => {
+                let (name, version) = value.split_once('/').unwrap_or((value, ""));
+                let separator = if value.contains('/') { "/" } else { "" };
+                name.eq_ignore_ascii_case(old)
+                    .then(|| format!("Document={}{}{}", new, separator, version))
+            }
+            (Some("Workspace"), Some((key, value))) if key.eq_ignore_ascii_case(old) => {
+                Some(format!("{}={}", new, value))
+            }
+            _ => None,
+        };
+        result.push_str(renamed.as_deref().unwrap_or(content));
+        result.push_str(terminator);
+    }
+
+    result
+}
+
+/// Parses a `HostExtenderRef` line, e.g.
+/// `&H00000001={3832D640-CF90-11CF-8E43-00A0C911005A};VBE;&H00000000`.
+fn parse_host_extender(key: &str, value: &str) -> Option<HostExtender> {
+    let mut fields = value.split(';');
+    let guid = fields.next()?;
+    let lib_name = fields.next()?;
+    let flags = fields.next()?;
+
+    Some(HostExtender {
+        index: parse_hex(key)?,
+        guid: guid.to_owned(),
+        lib_name: lib_name.to_owned(),
+        flags: parse_hex(flags)?,
+    })
+}
+
+/// Parses a hexadecimal number in VBA notation, e.g. `&H00000001`.
+fn parse_hex(value: &str) -> Option<u32> {
+    let digits = value
+        .strip_prefix("&H")
+        .or_else(|| value.strip_prefix("&h"))?;
+    u32::from_str_radix(digits, 16).ok()
+}
+
+/// Decrypts a hex-encoded value encrypted as described in \[MS-OVBA\] section 2.4.3.
+///
+/// Error offsets are relative to the start of the hex-decoded value.
+pub(crate) fn decrypt(value: &str) -> Result<Vec<u8>> {
+    let error = |offset, kind| Error::Parser {
+        offset,
+        kind,
+        record: None,
+    };
+
+    let data = value
+        .as_bytes()
+        .chunks(2)
+        .enumerate()
+        .map(|(offset, digits)| {
+            std::str::from_utf8(digits)
+                .ok()
+                .filter(|digits| digits.len() == 2)
+                .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+                .ok_or_else(|| error(offset, ParseErrorKind::UnexpectedValue))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let (seed, version_enc, project_key_enc) = match data[..] {
+        [seed, version_enc, project_key_enc, ..] => (seed, version_enc, project_key_enc),
+        _ => return Err(error(data.len(), ParseErrorKind::UnexpectedEof)),
+    };
+    if seed ^ version_enc != 2 {
+        return Err(error(1, ParseErrorKind::UnexpectedValue));
+    }
+
+    let mut unencrypted_byte_1 = seed ^ project_key_enc;
+    let mut encrypted_byte_1 = project_key_enc;
+    let mut encrypted_byte_2 = version_enc;
+    let mut decrypted = data[3..].iter().map(|&byte_enc| {
+        let byte = byte_enc ^ encrypted_byte_2.wrapping_add(unencrypted_byte_1);
+        encrypted_byte_2 = encrypted_byte_1;
+        encrypted_byte_1 = byte_enc;
+        unencrypted_byte_1 = byte;
+        byte
+    });
+
+    // The ignored bytes are followed by the 32-bit length of the data.
+    let ignored_length = usize::from((seed & 6) / 2);
+    let header_length = 3 + ignored_length + 4;
+    let length = decrypted
+        .by_ref()
+        .skip(ignored_length)
+        .take(4)
+        .enumerate()
+        .fold(0_usize, |length, (index, byte)| {
+            length | usize::from(byte) << (8 * index)
+        });
+    let result = decrypted.collect::<Vec<_>>();
+    if data.len() < header_length || result.len() < length {
+        return Err(error(data.len(), ParseErrorKind::UnexpectedEof));
+    }
+    if result.len() > length {
+        return Err(error(header_length + length, ParseErrorKind::TrailingData));
+    }
+
+    Ok(result)
+}
+
+/// Removes the enclosing quotation marks from `value`, if present.
+fn unquote(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .unwrap_or(value)
+}
+
+impl ProjectProperties {
+    fn push_module(&mut self, name: &str, kind: ModuleKind) {
+        self.modules.push(name.to_owned());
+        self.module_kinds.push(kind);
+    }
+}
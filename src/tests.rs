@@ -1,97 +1,466 @@
-use super::parser::{decompress, parse_project_information};
-
-#[test]
-fn copy_token_decoder() {
-    // CopyTokens store offset and length information in a single 16-bit value. The bit
-    // range used for either one changes with the current position in the output stream.
-    //
-    // This test verifies the implementation by running the decompressor against input
-    // where the first CopyToken is encountered at positions 31, 32, and 33 in the
-    // output stream.
-    //
-    // The input was generated using Excel 2013, by adding non-repeating character
-    // sequences to a module, until the full size reached the desired length.
-    // The prefix `Attribute VB_Name = "a"\r\n` gets added by Excel for every code
-    // module, where `"a"` is the respective module name.
-    // The resulting *vbaProject.bin* files were then extracted from the Excel documents,
-    // opened in a hex editor, and the byte sequences corresponding to the respective
-    // compressed containers copied here.
-
-    // CompressedContainer with first CopyToken at position 31:
-    // 01 27 B0 00 41 74 74 72 69 62 75 74 00 65 20 56 42 5F 4E 61 6D 00 65 20 3D 20 22 61 22 0D 80 0A 61 62 63 64 65 66 06 F0 00 0D 0A
-    const CONTAINER_1: &[u8] = b"\x01\x27\xB0\x00\x41\x74\x74\x72\x69\x62\x75\x74\x00\x65\x20\x56\x42\x5F\x4E\x61\x6D\x00\x65\x20\x3D\x20\x22\x61\x22\x0D\x80\x0A\x61\x62\x63\x64\x65\x66\x06\xF0\x00\x0D\x0A";
-    const CONTENTS_1: &[u8] = b"Attribute VB_Name = \"a\"\x0D\x0AabcdefAttribute\x0D\x0A";
-    let contents = decompress(CONTAINER_1).unwrap().1;
-    assert_eq!(contents, CONTENTS_1);
-
-    // CompressedContainer with first CopyToken at position 32:
-    // 01 28 B0 00 41 74 74 72 69 62 75 74 00 65 20 56 42 5F 4E 61 6D 00 65 20 3D 20 22 61 22 0D 00 0A 61 62 63 64 65 66 67 01 06 F8 0D 0A
-    const CONTAINER_2: &[u8] = b"\x01\x28\xB0\x00\x41\x74\x74\x72\x69\x62\x75\x74\x00\x65\x20\x56\x42\x5F\x4E\x61\x6D\x00\x65\x20\x3D\x20\x22\x61\x22\x0D\x00\x0A\x61\x62\x63\x64\x65\x66\x67\x01\x06\xF8\x0D\x0A";
-    const CONTENTS_2: &[u8] = b"Attribute VB_Name = \"a\"\x0D\x0AabcdefgAttribute\x0D\x0A";
-    let contents = decompress(CONTAINER_2).unwrap().1;
-    assert_eq!(contents, CONTENTS_2);
-
-    // CompressedContainer with first CopyToken at position 33:
-    // 01 29 B0 00 41 74 74 72 69 62 75 74 00 65 20 56 42 5F 4E 61 6D 00 65 20 3D 20 22 61 22 0D 00 0A 61 62 63 64 65 66 67 02 68 06 80 0D 0A
-    const CONTAINER_3: &[u8] = b"\x01\x29\xB0\x00\x41\x74\x74\x72\x69\x62\x75\x74\x00\x65\x20\x56\x42\x5F\x4E\x61\x6D\x00\x65\x20\x3D\x20\x22\x61\x22\x0D\x00\x0A\x61\x62\x63\x64\x65\x66\x67\x02\x68\x06\x80\x0D\x0A";
-    const CONTENTS_3: &[u8] = b"Attribute VB_Name = \"a\"\x0D\x0AabcdefghAttribute\x0D\x0A";
-    let contents = decompress(CONTAINER_3).unwrap().1;
-    assert_eq!(contents, CONTENTS_3);
-}
-
-#[test]
-fn proj_info_opt_records() {
-    // Version 11 of the `[MS-OVBA]` specification introduced an optional
-    // `PROJECTCOMPATVERSION` record following the `PROJECTSYSKIND` record. This test
-    // verifies that this addition is properly handled by the parser.
-    //
-    // In addition, this test verifies that the final `PROJECTCONSTANTS` is treated as
-    // optional (which it should have been all along).
-    //
-    // The four test inputs represent the `2x2` matrix of combinations of optional
-    // records.
-
-    const INPUT_NONE_NONE: &[u8] = b"\x01\x00\x04\x00\x00\x00\x02\x00\x00\x00\
-        \x02\x00\x04\x00\x00\x00\x09\x04\x00\x00\
-        \x14\x00\x04\x00\x00\x00\x09\x04\x00\x00\
-        \x03\x00\x02\x00\x00\x00\xE4\x04\
-        \x04\x00\x01\x00\x00\x00\x41\
-        \x05\x00\x01\x00\x00\x00\x41\x40\x00\x02\x00\x00\x00\x41\x00\
-        \x06\x00\x00\x00\x00\x00\x3D\x00\x00\x00\x00\x00\
-        \x07\x00\x04\x00\x00\x00\x00\x00\x00\x00\
-        \x08\x00\x04\x00\x00\x00\x00\x00\x00\x00\
-        \x09\x00\x04\x00\x00\x00\x00\x00\x00\x00\x00\x00\
-        \x0F\x00\x02\x00\x00\x00\x00\x00\
-        \x13\x00\x02\x00\x00\x00\xFF\xFF\
-        \x10\x00\
-        \x00\x00\x00\x00";
-    let res = parse_project_information(INPUT_NONE_NONE);
-    assert!(res.is_ok());
-    let res = res.unwrap();
-    assert!(res.1.information.constants.is_none());
+use super::alloc_tests::{
+    compress_literals, control_reference, dir_stream, dir_stream_with_references,
+};
+use super::attributes::split_attributes;
+use super::parser::{
+    compress, cp_into_string, cp_to_str, cp_to_string, decompress, decompress_bounded,
+    decompressor_error, parse_project_information,
+};
+use super::{
+    open_project, open_project_bytes, open_project_from, open_project_reader_at,
+    open_project_with_container, open_project_with_observer, open_project_with_path,
+    predicted_decompressed_size, raw_dir_from_reader, CacheStatus, Decompressor, Error,
+    HostExtender, ModuleKind, OpenOptions, ParseErrorKind, ParseEvent, Project, ProtectionState,
+    Reference, ReferenceProject, Warning,
+};
 
-    const INPUT_NONE_SOME: &[u8] = b"\x01\x00\x04\x00\x00\x00\x02\x00\x00\x00\
-        \x02\x00\x04\x00\x00\x00\x09\x04\x00\x00\
-        \x14\x00\x04\x00\x00\x00\x09\x04\x00\x00\
-        \x03\x00\x02\x00\x00\x00\xE4\x04\
-        \x04\x00\x01\x00\x00\x00\x41\
-        \x05\x00\x01\x00\x00\x00\x41\x40\x00\x02\x00\x00\x00\x41\x00\
-        \x06\x00\x00\x00\x00\x00\x3D\x00\x00\x00\x00\x00\
-        \x07\x00\x04\x00\x00\x00\x00\x00\x00\x00\
-        \x08\x00\x04\x00\x00\x00\x00\x00\x00\x00\
-        \x09\x00\x04\x00\x00\x00\x00\x00\x00\x00\x00\x00\
-        \x0C\x00\x00\x00\x00\x00\x3C\x00\x00\x00\x00\x00\
-        \x0F\x00\x02\x00\x00\x00\x00\x00\
-        \x13\x00\x02\x00\x00\x00\xFF\xFF\
-        \x10\x00\
-        \x00\x00\x00\x00";
-    let res = parse_project_information(INPUT_NONE_SOME);
-    assert!(res.is_ok());
-    let res = res.unwrap();
-    assert!(res.1.information.constants.is_some());
+use cfb::{CompoundFile, Version};
+use std::{
+    borrow::Cow,
+    io::{Cursor, Read, Write},
+    path::{Path, PathBuf},
+};
+
+// -------------------------------------------------------------------------
+// Test helpers
+// -------------------------------------------------------------------------
+
+/// Builds a CFB container holding the (decompressed) `dir` stream in the *VBA* storage,
+/// along with additional `streams` as `(path, data)` tuples.
+fn project_container(dir: &[u8], streams: &[(&str, &[u8])]) -> Vec<u8> {
+    project_container_with_version(Version::V3, dir, streams)
+}
+
+/// Same as `project_container`, using a specific CFB version.
+fn project_container_with_version(
+    version: Version,
+    dir: &[u8],
+    streams: &[(&str, &[u8])],
+) -> Vec<u8> {
+    let mut container =
+        CompoundFile::create_with_version(version, Cursor::new(Vec::new())).unwrap();
+    container.create_storage("/VBA").unwrap();
+    container
+        .create_stream("/VBA/dir")
+        .unwrap()
+        .write_all(&compress_literals(dir))
+        .unwrap();
+    for (path, data) in streams {
+        if let Some(parent) = Path::new(path).parent() {
+            container.create_storage_all(parent).unwrap();
+        }
+        container
+            .create_stream(path)
+            .unwrap()
+            .write_all(data)
+            .unwrap();
+    }
+    container.flush().unwrap();
+    container.into_inner().into_inner()
+}
+
+/// Encrypts `data` as described in \[MS-OVBA\] section 2.4.3.2, and returns the
+/// hex-encoded result.
+fn encrypt(seed: u8, project_key: u8, data: &[u8]) -> String {
+    let version_enc = seed ^ 2;
+    let project_key_enc = seed ^ project_key;
+    let mut result = vec![seed, version_enc, project_key_enc];
+
+    let mut unencrypted_byte_1 = project_key;
+    let mut encrypted_byte_1 = project_key_enc;
+    let mut encrypted_byte_2 = version_enc;
+    let ignored = vec![0x07; usize::from((seed & 6) / 2)];
+    let length = (data.len() as u32).to_le_bytes();
+    for &byte in ignored.iter().chain(&length).chain(data) {
+        let byte_enc = byte ^ encrypted_byte_2.wrapping_add(unencrypted_byte_1);
+        result.push(byte_enc);
+        encrypted_byte_2 = encrypted_byte_1;
+        encrypted_byte_1 = byte_enc;
+        unencrypted_byte_1 = byte;
+    }
+
+    result.iter().map(|byte| format!("{:02X}", byte)).collect()
+}
+
+// -------------------------------------------------------------------------
+// -------------------------------------------------------------------------
+
+#[test]
+fn module_stream_name_separators() {
+    // The stream name of module "b" resolves to the */PROJECT* stream when naively
+    // joined to the *VBA* storage path. This must be rejected.
+    const SOURCE: &[u8] = b"Attribute VB_Name = \"a\"\r\n";
+    let dir = dir_stream(&[("a", "a", 0), ("b", "../PROJECT", 0)]);
+    let module = compress_literals(SOURCE);
+    let raw = project_container(&dir, &[("/VBA/a", &module), ("/PROJECT", &module)]);
+    let project = open_project(raw).unwrap();
+
+    assert_eq!(project.module_source_raw("a").unwrap(), SOURCE);
+    assert!(matches!(
+        project.module_source_raw("b"),
+        Err(Error::InvalidStreamName(name)) if name == "../PROJECT"
+    ));
+}
+
+#[test]
+fn module_source_preview() {
+    // Source spanning several chunks
+    let source = "Attribute VB_Name = \"a\"\r\n".repeat(400);
+    let dir = dir_stream(&[("a", "a", 0)]);
+    let module = compress_literals(source.as_bytes());
+    let raw = project_container(&dir, &[("/VBA/a", &module)]);
+    let project = open_project(raw).unwrap();
+
+    assert_eq!(
+        project.module_source_preview("a", 14).unwrap(),
+        "Attribute VB_N"
+    );
+    assert_eq!(
+        project.module_source_preview("a", 5000).unwrap(),
+        source[..5000]
+    );
+    assert_eq!(
+        project.module_source_preview("a", usize::MAX).unwrap(),
+        source
+    );
+}
+
+#[test]
+fn text_offset_out_of_range() {
+    // A MODULEOFFSET past the end of the module stream
+    const OFFSET: u32 = 0x7fff_0000;
+    let dir = dir_stream(&[("a", "a", OFFSET)]);
+    let raw = project_container(&dir, &[("/VBA/a", &compress(b"Sub A()"))]);
+    let project = open_project(raw).unwrap();
+
+    let check = |result: Result<String, Error>| match result {
+        Err(e @ Error::Module { .. }) => assert_eq!(e.offset(), Some(OFFSET as usize)),
+        _ => panic!("expected a Module error"),
+    };
+    check(project.module_source("a"));
+    check(project.module_source_preview("a", 10));
+    check(project.sources().next().unwrap().map(|(_, source)| source));
+    #[cfg(feature = "rayon")]
+    check(project.sources_par().remove(0).map(|(_, source)| source));
+}
+
+#[test]
+fn module_error() {
+    // The module stream for "b" holds an invalid `CompressedContainer` signature.
+    let dir = dir_stream(&[("a", "a", 0), ("b", "b", 0)]);
+    let module = compress_literals(b"Attribute VB_Name = \"a\"\r\n");
+    let raw = project_container(&dir, &[("/VBA/a", &module), ("/VBA/b", b"\x02\x00")]);
+    let project = open_project(raw).unwrap();
+
+    assert!(project.module_source_raw("a").is_ok());
+    let err = project.module_source_raw("b").unwrap_err();
+    assert!(matches!(
+        &err,
+        Error::Module { name, source }
+            if name == "b" && matches!(**source, Error::Decompressor { offset: 0 })
+    ));
+    assert_eq!(err.offset(), Some(0));
+}
+
+#[test]
+fn content_checksum() {
+    // The same modules, stored in a different order, and at different offsets into the
+    // module streams.
+    let source_a = b"Attribute VB_Name = \"a\"\r\n";
+    let source_b = b"Attribute VB_Name = \"b\"\r\n";
+    let module_a = compress_literals(source_a);
+    let module_b = [b"p-code".as_ref(), &compress_literals(source_b)].concat();
+
+    let dir = dir_stream(&[("a", "a", 0), ("b", "b", 6)]);
+    let raw = project_container(&dir, &[("/VBA/a", &module_a), ("/VBA/b", &module_b)]);
+    let checksum_1 = open_project(raw).unwrap().content_checksum().unwrap();
+
+    let dir = dir_stream(&[("b", "b", 0), ("a", "a", 0)]);
+    let module_b = compress_literals(source_b);
+    let raw = project_container(&dir, &[("/VBA/b", &module_b), ("/VBA/a", &module_a)]);
+    let checksum_2 = open_project(raw).unwrap().content_checksum().unwrap();
+
+    assert_eq!(checksum_1, checksum_2);
+
+    // A different module source produces a different checksum.
+    let dir = dir_stream(&[("a", "a", 0), ("b", "b", 0)]);
+    let raw = project_container(&dir, &[("/VBA/a", &module_a), ("/VBA/b", &module_a)]);
+    let checksum_3 = open_project(raw).unwrap().content_checksum().unwrap();
+
+    assert_ne!(checksum_1, checksum_3);
+
+    // Modules whose names differ only in case contribute their own source code.
+    let dir = dir_stream(&[("a", "s1", 0), ("A", "s2", 0)]);
+    let raw = project_container(&dir, &[("/VBA/s1", &module_a), ("/VBA/s2", &module_b)]);
+    let checksum_4 = open_project(raw).unwrap().content_checksum().unwrap();
+    let raw = project_container(&dir, &[("/VBA/s1", &module_a), ("/VBA/s2", &module_a)]);
+    let checksum_5 = open_project(raw).unwrap().content_checksum().unwrap();
+
+    assert_ne!(checksum_4, checksum_5);
+}
+
+#[test]
+fn cfb_version_4() {
+    // Version 4 CFBs use 4096-byte sectors.
+    let source = "Attribute VB_Name = \"a\"\r\n".repeat(400);
+    let dir = dir_stream(&[("a", "a", 0)]);
+    let module = compress_literals(source.as_bytes());
+    let raw = project_container_with_version(Version::V4, &dir, &[("/VBA/a", &module)]);
+    let project = open_project(raw).unwrap();
+
+    assert_eq!(project.with_container(|cfb| cfb.version()), Version::V4);
+    assert_eq!(project.module_source("a").unwrap(), source);
+}
+
+#[test]
+fn module_source_trimmed() {
+    const SOURCE: &[u8] = b"Attribute VB_Name = \"a\"  \r\n\r\nSub A()\t\r\r\nEnd Sub\r\n \r\n\r\n";
+    let dir = dir_stream(&[("a", "a", 0)]);
+    let module = compress_literals(SOURCE);
+    let raw = project_container(&dir, &[("/VBA/a", &module)]);
+    let project = open_project(raw).unwrap();
+
+    assert_eq!(
+        project.module_source_trimmed("a").unwrap(),
+        "Attribute VB_Name = \"a\"\r\n\r\nSub A()\r\nEnd Sub\r\n"
+    );
+}
+
+#[test]
+fn module_source_normalized() {
+    const SOURCE: &[u8] = b"Sub A()\r\n  s = \"a\rb\"\r\nEnd Sub\r\n";
+    let dir = dir_stream(&[("a", "a", 0)]);
+    let module = compress_literals(SOURCE);
+    let raw = project_container(&dir, &[("/VBA/a", &module)]);
+    let project = open_project(raw).unwrap();
+
+    assert_eq!(
+        project.module_source_normalized("a").unwrap(),
+        "Sub A()\n  s = \"a\rb\"\nEnd Sub\n"
+    );
+    assert_eq!(
+        project.modules[0]
+            .source_lines(&project)
+            .unwrap()
+            .iter()
+            .collect::<Vec<_>>(),
+        ["Sub A()", "  s = \"a\rb\"", "End Sub"]
+    );
+}
+
+#[test]
+fn source_lines_continuation() {
+    const SOURCE: &[u8] =
+        b"Sub A()\r\n  s = \"a\" & _\r\n      \"b\" _\r\n  & \"c\"\r\n  t = a_\r\nEnd Sub _\r\n";
+    let dir = dir_stream(&[("a", "a", 0)]);
+    let raw = project_container(&dir, &[("/VBA/a", &compress_literals(SOURCE))]);
+    let project = open_project(raw).unwrap();
+
+    let lines = project.modules[0].source_lines(&project).unwrap();
+    assert_eq!(
+        lines.iter().collect::<Vec<_>>(),
+        [
+            "Sub A()",
+            "  s = \"a\" & \"b\" & \"c\"",
+            "  t = a_",
+            "End Sub _"
+        ]
+    );
+    assert!(matches!(
+        lines.iter().next(),
+        Some(Cow::Borrowed("Sub A()"))
+    ));
+    assert_eq!(lines.as_str().as_bytes(), SOURCE);
+}
+
+#[test]
+fn reference_project_resolved_libid() {
+    let reference = ReferenceProject {
+        name: None,
+        libid_absolute: r"*\AC:\Users\Public\Shared\Library.xlsm".to_owned(),
+        libid_relative: r"*\AShared\Library.xlsm".to_owned(),
+        major_version: 0,
+        minor_version: 0,
+    };
+    assert_eq!(
+        reference.resolved_libid(Path::new("docs")),
+        Path::new("docs").join("Shared").join("Library.xlsm")
+    );
+}
+
+#[test]
+fn discover_projects() {
+    let dir = compress_literals(&dir_stream(&[]));
+    let raw = project_container(
+        &dir_stream(&[]),
+        &[("/Macros/VBA/dir", &dir), ("/Other/dir", &dir)],
+    );
+    let container = CompoundFile::open(Cursor::new(raw)).unwrap();
+
+    let mut roots = Project::discover_projects(&container).unwrap();
+    roots.sort();
+    assert_eq!(
+        roots,
+        vec![
+            Path::new("/").to_string_lossy(),
+            Path::new("/Macros").to_string_lossy()
+        ]
+    );
+}
+
+#[test]
+fn autodetect_vba_storage() {
+    let dir = dir_stream(&[("a", "a", 0)]);
+    let mut container = CompoundFile::create(Cursor::new(Vec::new())).unwrap();
+    // A *dir* stream without a *_VBA_PROJECT* stream doesn't identify a VBA storage.
+    container.create_storage_all("/Decoy/VBA").unwrap();
+    container.create_stream("/Decoy/VBA/dir").unwrap();
+    container
+        .create_storage_all("/_VBA_PROJECT_CUR/VBA")
+        .unwrap();
+    container
+        .create_stream("/_VBA_PROJECT_CUR/VBA/dir")
+        .unwrap()
+        .write_all(&compress_literals(&dir))
+        .unwrap();
+    container
+        .create_stream("/_VBA_PROJECT_CUR/VBA/_VBA_PROJECT")
+        .unwrap();
+    container.flush().unwrap();
+
+    assert_eq!(
+        super::locate_vba_project(&container),
+        Some(PathBuf::from("/_VBA_PROJECT_CUR/VBA"))
+    );
+
+    // `open_project` falls back to the located storage if there's no */VBA/dir* stream.
+    let raw = container.into_inner().into_inner();
+    let project = open_project(raw).unwrap();
+    assert_eq!(project.root(), Path::new("/_VBA_PROJECT_CUR"));
+    assert_eq!(project.module_names().collect::<Vec<_>>(), ["a"]);
+
+    let raw = project_container(&dir_stream(&[]), &[]);
+    let container = CompoundFile::open(Cursor::new(raw)).unwrap();
+    assert_eq!(super::locate_vba_project(&container), None);
+}
+
+#[test]
+fn reload() {
+    let dir = dir_stream(&[("a", "a", 0)]);
+    let raw = project_container(&dir, &[]);
+    let mut project = open_project(raw).unwrap();
+    assert_eq!(project.module_names().collect::<Vec<_>>(), ["a"]);
+
+    // Replace the *dir* stream in place, as if the underlying file had changed.
+    let dir = dir_stream(&[("a", "a", 0), ("b", "b", 0)]);
+    project
+        .container
+        .get_mut()
+        .as_mut()
+        .unwrap()
+        .create_stream("/VBA/dir")
+        .unwrap()
+        .write_all(&compress_literals(&dir))
+        .unwrap();
+    project.reload().unwrap();
+    assert_eq!(project.module_names().collect::<Vec<_>>(), ["a", "b"]);
+
+    // A failed reload leaves the project unchanged.
+    project
+        .container
+        .get_mut()
+        .as_mut()
+        .unwrap()
+        .create_stream("/VBA/dir")
+        .unwrap()
+        .write_all(&compress_literals(b"\x01\x00"))
+        .unwrap();
+    assert!(project.reload().is_err());
+    assert_eq!(project.module_names().collect::<Vec<_>>(), ["a", "b"]);
+}
 
-    const INPUT_SOME_NONE: &[u8] = b"\x01\x00\x04\x00\x00\x00\x02\x00\x00\x00\
-        \x4A\x00\x04\x00\x00\x00\x01\x02\x03\x04\
+#[test]
+fn uncompressed_chunk_size() {
+    // The data of an uncompressed chunk is 4096 bytes, irrespective of the size stored in
+    // the header.
+    let mut data = vec![0x01, 0x09, 0x30];
+    data.extend_from_slice(&[b'A'; 4096]);
+    data.extend_from_slice(&[0x02, 0xb0, 0x00, b'B', b'C']);
+    let expected = [&[b'A'; 4096][..], b"BC"].concat();
+    assert_eq!(decompress(&data).unwrap().1, expected);
+    assert_eq!(predicted_decompressed_size(&data).unwrap(), 2 * 4096);
+
+    let mut output = Vec::new();
+    Decompressor::new(&data[..])
+        .read_to_end(&mut output)
+        .unwrap();
+    assert_eq!(output, expected);
+
+    // A final uncompressed chunk can hold less data.
+    let data = [0x01, 0xff, 0x3f, b'A', b'B'];
+    assert_eq!(decompress(&data).unwrap().1, b"AB");
+
+    let mut output = Vec::new();
+    Decompressor::new(&data[..])
+        .read_to_end(&mut output)
+        .unwrap();
+    assert_eq!(output, b"AB");
+
+    // `compress` stores incompressible chunks uncompressed.
+    let mut state = 0x1234_5678_u32;
+    let noise = (0..8000)
+        .map(|_| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (state >> 16) as u8
+        })
+        .collect::<Vec<_>>();
+    let container = compress(&noise);
+    assert_eq!(container[1..3], [0xff, 0x3f]);
+    assert_eq!(container[4099..4101], [0xff, 0x3f]);
+    assert_eq!(decompress(&container).unwrap().1, noise);
+}
+
+#[test]
+fn set_module_flags() {
+    let dir = dir_stream(&[("a", "a", 0)]);
+    let raw = project_container(&dir, &[]);
+    let mut project = open_project(raw).unwrap();
+    assert!(!project.modules[0].read_only && !project.modules[0].private);
+
+    project.set_module_flags("a", true, true).unwrap();
+    assert!(project.modules[0].read_only && project.modules[0].private);
+
+    let err = project.set_module_flags("b", true, false).unwrap_err();
+    assert!(matches!(err, Error::ModuleNotFound(name) if name == "b"));
+
+    project.reload().unwrap();
+    assert!(!project.modules[0].read_only && !project.modules[0].private);
+
+    // The flags are persisted by `write_to`.
+    project.set_module_flags("a", true, false).unwrap();
+    let mut written = Cursor::new(Vec::new());
+    project.write_to(&mut written).unwrap();
+    let project = open_project(written.into_inner()).unwrap();
+    assert!(project.modules[0].read_only && !project.modules[0].private);
+}
+
+#[test]
+fn open_project_at_offset() {
+    let dir = dir_stream(&[("a", "a", 0)]);
+    let raw = [b"carved".as_ref(), &project_container(&dir, &[])].concat();
+
+    let project = open_project_reader_at(Cursor::new(&raw), 6).unwrap();
+    assert_eq!(project.module_names().collect::<Vec<_>>(), ["a"]);
+
+    assert!(matches!(
+        open_project_reader_at(Cursor::new(&raw), 0),
+        Err(Error::Cfb(_))
+    ));
+}
+
+#[test]
+fn reference_empty_libid() {
+    const INFORMATION: &[u8] = b"\x01\x00\x04\x00\x00\x00\x02\x00\x00\x00\
         \x02\x00\x04\x00\x00\x00\x09\x04\x00\x00\
         \x14\x00\x04\x00\x00\x00\x09\x04\x00\x00\
         \x03\x00\x02\x00\x00\x00\xE4\x04\
@@ -100,16 +469,52 @@ fn proj_info_opt_records() {
         \x06\x00\x00\x00\x00\x00\x3D\x00\x00\x00\x00\x00\
         \x07\x00\x04\x00\x00\x00\x00\x00\x00\x00\
         \x08\x00\x04\x00\x00\x00\x00\x00\x00\x00\
-        \x09\x00\x04\x00\x00\x00\x00\x00\x00\x00\x00\x00\
-        \x0F\x00\x02\x00\x00\x00\x00\x00\
+        \x09\x00\x04\x00\x00\x00\x00\x00\x00\x00\x00\x00";
+    // Two `REFERENCEREGISTERED` records, the first one with an empty libid.
+    const REFERENCES: &[u8] = b"\x0D\x00\x0A\x00\x00\x00\x00\x00\x00\x00\
+        \x00\x00\x00\x00\x00\x00\
+        \x0D\x00\x19\x00\x00\x00\x0F\x00\x00\x00*\\G{00020430}#2\
+        \x00\x00\x00\x00\x00\x00";
+    const MODULES: &[u8] = b"\x0F\x00\x02\x00\x00\x00\x00\x00\
         \x13\x00\x02\x00\x00\x00\xFF\xFF\
         \x10\x00\
         \x00\x00\x00\x00";
-    let res = parse_project_information(INPUT_SOME_NONE);
-    assert!(res.is_ok());
+    let dir = [INFORMATION, REFERENCES, MODULES].concat();
+    let raw = project_container(&dir, &[]);
+    let project = open_project(raw).unwrap();
+
+    assert!(matches!(
+        &project.references[0],
+        Reference::Registered(reference) if reference.libid.is_empty()
+    ));
+    assert_eq!(
+        project.reference_libids().collect::<Vec<_>>(),
+        ["", "*\\G{00020430}#2"]
+    );
+}
+
+#[test]
+fn module_source_no_nul() {
+    const SOURCE: &[u8] =
+        b"Attribute VB_Name = \"a\"\r\nSub A()\0\r\nMsgBox \"\0\0\"\r\nEnd Sub\r\n";
+    let dir = dir_stream(&[("a", "a", 0)]);
+    let module = compress_literals(SOURCE);
+    let raw = project_container(&dir, &[("/VBA/a", &module)]);
+    let project = open_project(raw).unwrap();
+
+    assert_eq!(project.module_source("a").unwrap().matches('\0').count(), 3);
+    assert_eq!(
+        project.module_source_no_nul("a").unwrap(),
+        (
+            "Attribute VB_Name = \"a\"\r\nSub A()\r\nMsgBox \"\"\r\nEnd Sub\r\n".to_owned(),
+            3
+        )
+    );
+}
 
-    const INPUT_SOME_SOME: &[u8] = b"\x01\x00\x04\x00\x00\x00\x02\x00\x00\x00\
-        \x4A\x00\x04\x00\x00\x00\x01\x02\x03\x04\
+#[test]
+fn lenient_skipped_references() {
+    const INFORMATION: &[u8] = b"\x01\x00\x04\x00\x00\x00\x02\x00\x00\x00\
         \x02\x00\x04\x00\x00\x00\x09\x04\x00\x00\
         \x14\x00\x04\x00\x00\x00\x09\x04\x00\x00\
         \x03\x00\x02\x00\x00\x00\xE4\x04\
@@ -118,12 +523,1578 @@ fn proj_info_opt_records() {
         \x06\x00\x00\x00\x00\x00\x3D\x00\x00\x00\x00\x00\
         \x07\x00\x04\x00\x00\x00\x00\x00\x00\x00\
         \x08\x00\x04\x00\x00\x00\x00\x00\x00\x00\
-        \x09\x00\x04\x00\x00\x00\x00\x00\x00\x00\x00\x00\
-        \x0C\x00\x00\x00\x00\x00\x3C\x00\x00\x00\x00\x00\
-        \x0F\x00\x02\x00\x00\x00\x00\x00\
+        \x09\x00\x04\x00\x00\x00\x00\x00\x00\x00\x00\x00";
+    // Two named `REFERENCEREGISTERED` records, the first one with a non-zero `Reserved1`
+    // field.
+    const REFERENCES: &[u8] = b"\x16\x00\x03\x00\x00\x00bad\x3E\x00\x00\x00\x00\x00\
+        \x0D\x00\x19\x00\x00\x00\x0F\x00\x00\x00*\\G{00000000}#0\
+        \x01\x00\x00\x00\x00\x00\
+        \x16\x00\x06\x00\x00\x00stdole\x3E\x00\x00\x00\x00\x00\
+        \x0D\x00\x19\x00\x00\x00\x0F\x00\x00\x00*\\G{00020430}#2\
+        \x00\x00\x00\x00\x00\x00";
+    const MODULES: &[u8] = b"\x0F\x00\x02\x00\x00\x00\x00\x00\
         \x13\x00\x02\x00\x00\x00\xFF\xFF\
         \x10\x00\
         \x00\x00\x00\x00";
-    let res = parse_project_information(INPUT_SOME_SOME);
-    assert!(res.is_ok());
+    let dir = [INFORMATION, REFERENCES, MODULES].concat();
+
+    let raw = project_container(&dir, &[]);
+    assert!(matches!(
+        open_project(raw.clone()),
+        Err(Error::Parser {
+            kind: ParseErrorKind::UnexpectedTag,
+            record: Some("REFERENCEREGISTERED"),
+            ..
+        })
+    ));
+
+    let project = OpenOptions::new().lenient(true).open(raw).unwrap();
+    assert_eq!(project.skipped_reference_count(), 1);
+    assert_eq!(
+        project.warnings(),
+        [Warning::SkippedReference {
+            offset: INFORMATION.len()
+        }]
+    );
+    assert_eq!(project.references.len(), 1);
+    assert!(project.reference_by_name("stdole").is_some());
+}
+
+#[test]
+fn information_accessors() {
+    let dir = dir_stream(&[]);
+    let raw = project_container(&dir, &[]);
+    let project = open_project(raw).unwrap();
+    let information = &project.information;
+
+    assert_eq!(information.compat_version(), None);
+    assert_eq!(information.lcid(), 0x0409);
+    assert_eq!(information.lcid_invoke(), 0x0409);
+    assert_eq!(information.name(), "VBAProject");
+    assert_eq!(information.doc_string(), "");
+    assert_eq!(information.help_file(), "");
+    assert_eq!(information.help_context(), 0);
+    assert_eq!(information.lib_flags(), 0);
+    assert_eq!(information.version_major(), 1);
+    assert_eq!(information.version_minor(), 0);
+    assert_eq!(information.version(), (1, 0));
+    assert_eq!(information.version_string(), "1.0");
+    assert_eq!(information.constants(), "");
+}
+
+#[test]
+fn project_open_path() {
+    let path = std::env::temp_dir().join(format!("ovba-project-open-{}.bin", std::process::id()));
+    let dir = dir_stream(&[("a", "a", 0)]);
+    std::fs::write(&path, project_container(&dir, &[])).unwrap();
+    let project = Project::open(&path);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(project.unwrap().module_names().collect::<Vec<_>>(), ["a"]);
+
+    assert!(matches!(Project::open(&path), Err(Error::Io(_))));
+}
+
+#[test]
+fn reference_accessors() {
+    let references = [
+        control_reference(
+            "MSForms",
+            b"\xE1\x2E\x45\x0D\x8F\xE0\x1A\x10\x85\x2E\x02\x60\x8C\x4D\x0B\xB4",
+        ),
+        b"\x0D\x00\x19\x00\x00\x00\x0F\x00\x00\x00*\\G{00020430}#2\
+            \x00\x00\x00\x00\x00\x00"
+            .to_vec(),
+    ]
+    .concat();
+    let dir = dir_stream_with_references(&references, &[]);
+    let raw = project_container(&dir, &[]);
+    let project = open_project(raw).unwrap();
+
+    assert_eq!(project.references.len(), 2);
+    assert_eq!(project.references[0].name(), Some("MSForms"));
+    let control = match &project.references[0] {
+        Reference::Control(control) => control,
+        _ => panic!("expected a control reference"),
+    };
+    assert_eq!(control.name(), Some("MSForms"));
+    assert_eq!(
+        control.libid_original(),
+        Some("*\\G{00020430-0000-0000-C000-000000000046}#2.0#0#stdole2.tlb#OLE Automation")
+    );
+    assert_eq!(
+        control.libid_twiddled(),
+        "*\\G{00000000-0000-0000-0000-000000000000}#0.0#0##"
+    );
+    assert_eq!(control.name_extended(), Some("ext"));
+    assert_eq!(
+        control.libid_extended(),
+        "*\\G{00000000-0000-0000-0000-000000000001}#2.0#0#ext.exd#Extended"
+    );
+    assert_eq!(
+        control.guid(),
+        b"\xE1\x2E\x45\x0D\x8F\xE0\x1A\x10\x85\x2E\x02\x60\x8C\x4D\x0B\xB4"
+    );
+    assert_eq!(
+        control.guid_string(),
+        "{0D452EE1-E08F-101A-852E-02608C4D0BB4}"
+    );
+    assert!(project.reference_by_guid(control.guid()).is_some());
+    assert_eq!(control.cookie(), 7);
+
+    assert_eq!(project.references[1].name(), None);
+    assert!(matches!(
+        &project.references[1],
+        Reference::Registered(registered) if registered.libid() == "*\\G{00020430}#2"
+    ));
+
+    let controls = project.control_references().collect::<Vec<_>>();
+    assert_eq!(controls.len(), 1);
+    assert!(std::ptr::eq(controls[0], control));
+    let registered = project.registered_references().collect::<Vec<_>>();
+    assert_eq!(registered.len(), 1);
+    assert_eq!(registered[0].libid(), "*\\G{00020430}#2");
+    assert_eq!(project.project_references().count(), 0);
+}
+
+#[test]
+fn code_page_borrowed() {
+    assert!(matches!(
+        cp_to_str(b"Sub A()", 1252),
+        Ok(Cow::Borrowed("Sub A()"))
+    ));
+    assert!(matches!(cp_to_str(b"Sub \xc4()", 1252), Ok(Cow::Owned(s)) if s == "Sub Ä()"));
+    // UTF-16 isn't ASCII-compatible
+    assert!(matches!(cp_to_str(b"A\0", 1200), Ok(Cow::Owned(s)) if s == "A"));
+
+    let data = b"Sub A()".to_vec();
+    let ptr = data.as_ptr();
+    let source = cp_into_string(data, 1252).unwrap();
+    assert_eq!((source.as_str(), source.as_ptr()), ("Sub A()", ptr));
+    assert_eq!(
+        cp_into_string(b"Sub \xc4()".to_vec(), 1252).unwrap(),
+        "Sub Ä()"
+    );
+    assert_eq!(cp_into_string(b"\x93\xfa".to_vec(), 932).unwrap(), "日");
+    assert_eq!(cp_into_string(b"A\0".to_vec(), 1200).unwrap(), "A");
+}
+
+#[test]
+fn open_project_from_file() {
+    let path = std::env::temp_dir().join(format!("ovba-open-from-{}.bin", std::process::id()));
+    const SOURCE: &[u8] = b"Attribute VB_Name = \"a\"\r\n";
+    let dir = dir_stream(&[("a", "a", 0)]);
+    let module = compress_literals(SOURCE);
+    std::fs::write(&path, project_container(&dir, &[("/VBA/a", &module)])).unwrap();
+
+    let file = std::fs::File::open(&path).unwrap();
+    let project = open_project_from(file).unwrap();
+    let source = project.module_source_raw("a");
+    drop(project);
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(source.unwrap(), SOURCE);
+}
+
+#[test]
+fn nested_project() {
+    const SOURCE: &[u8] = b"Attribute VB_Name = \"b\"\r\n";
+    let nested_dir = compress_literals(&dir_stream(&[("b", "b", 0)]));
+    let module = compress_literals(SOURCE);
+    let raw = project_container(
+        &dir_stream(&[]),
+        &[("/Macros/VBA/dir", &nested_dir), ("/Macros/VBA/b", &module)],
+    );
+
+    let project = open_project_with_path("/Macros", Cursor::new(raw.clone())).unwrap();
+    assert_eq!(project.root(), Path::new("/Macros"));
+    assert_eq!(project.module_names().collect::<Vec<_>>(), ["b"]);
+    assert_eq!(project.module_source_raw("b").unwrap(), SOURCE);
+    assert_eq!(project.read_stream("VBA/b").unwrap(), module);
+    assert_eq!(project.read_stream("/Macros/VBA/b").unwrap(), module);
+
+    // The root path is taken relative to the container's root.
+    let project = open_project_with_path("Macros", Cursor::new(raw.clone())).unwrap();
+    assert_eq!(project.module_names().collect::<Vec<_>>(), ["b"]);
+
+    let project = open_project(raw).unwrap();
+    assert_eq!(project.root(), Path::new("/"));
+    assert_eq!(project.module_names().count(), 0);
+}
+
+#[test]
+fn raw_module_stream() {
+    const SOURCE: &[u8] = b"Attribute VB_Name = \"a\"\r\n";
+    let stream = [b"p-code".as_ref(), &compress_literals(SOURCE)].concat();
+    let dir = dir_stream(&[("a", "a", 6), ("b", "b", 0)]);
+    let raw = project_container(&dir, &[("/VBA/a", &stream)]);
+    let project = open_project(raw).unwrap();
+
+    assert_eq!(project.raw_module_stream("a").unwrap(), stream);
+    // A missing stream isn't reported as a decompression failure.
+    assert!(matches!(
+        project.raw_module_stream("b"),
+        Err(Error::Module { name, source }) if name == "b" && matches!(*source, Error::Cfb(_))
+    ));
+    assert!(matches!(
+        project.raw_module_stream("c"),
+        Err(Error::ModuleNotFound(_))
+    ));
+}
+
+#[test]
+fn attributes_vba() {
+    const SOURCE: &str = "Attribute VB_Name = \"Class1\"\r\n\
+        Attribute VB_Base = \"0{FCFB3D2A-A0FA-1068-A738-08002B3371B5}\"\r\n\
+        attribute VB_GlobalNameSpace = False\r\n\
+        Attribute VB_Description = _\r\n    \"Say \"\"Hi\"\"\"\r\n\
+        Option Explicit\r\n\
+        Attribute Misplaced = True\r\n";
+    let (attributes, rest) = split_attributes(SOURCE);
+    assert_eq!(
+        attributes,
+        [
+            ("VB_Name".to_owned(), "Class1".to_owned()),
+            (
+                "VB_Base".to_owned(),
+                "0{FCFB3D2A-A0FA-1068-A738-08002B3371B5}".to_owned()
+            ),
+            ("VB_GlobalNameSpace".to_owned(), "False".to_owned()),
+            ("VB_Description".to_owned(), "Say \"Hi\"".to_owned()),
+        ]
+    );
+    assert_eq!(rest, "Option Explicit\r\nAttribute Misplaced = True\r\n");
+
+    // Attributes only, without a trailing line terminator
+    let (attributes, rest) = split_attributes("Attribute VB_Name = \"a\"");
+    assert_eq!(attributes, [("VB_Name".to_owned(), "a".to_owned())]);
+    assert_eq!(rest, "");
+}
+
+#[test]
+fn attributes_vb6() {
+    const SOURCE: &str = "VERSION 5.00\r\n\
+        Object = \"{831FDD16-0C5C-11D2-A9FC-0000F8754DA1}#2.0#0\"; \"MSCOMCTL.OCX\"\r\n\
+        Begin VB.Form Form1\r\n\
+        \x20  Caption = \"Form1\"\r\n\
+        \x20  BeginProperty Font\r\n\
+        \x20     Name = \"MS Sans Serif\"\r\n\
+        \x20  EndProperty\r\n\
+        \x20  Begin VB.CommandButton Command1\r\n\
+        \x20  End\r\n\
+        End\r\n\
+        Attribute VB_Name = \"Form1\"\r\n\
+        Attribute VB_GlobalNameSpace = False\r\n\
+        Attribute VB_Creatable = False\r\n\
+        Private Sub Command1_Click()\r\n\
+        End Sub\r\n";
+    let (attributes, rest) = split_attributes(SOURCE);
+    assert_eq!(
+        attributes,
+        [
+            ("VB_Name".to_owned(), "Form1".to_owned()),
+            ("VB_GlobalNameSpace".to_owned(), "False".to_owned()),
+            ("VB_Creatable".to_owned(), "False".to_owned()),
+        ]
+    );
+    assert_eq!(rest, "Private Sub Command1_Click()\r\nEnd Sub\r\n");
+
+    const CLASS: &str = "VERSION 1.0 CLASS\r\n\
+        BEGIN\r\n\
+        \x20 MultiUse = -1  'True\r\n\
+        END\r\n\
+        Attribute VB_Name = \"Class1\"\r\n\
+        Attribute VB_Exposed = False\r\n\
+        Option Explicit\r\n";
+    let (attributes, rest) = split_attributes(CLASS);
+    assert_eq!(
+        attributes,
+        [
+            ("VB_Name".to_owned(), "Class1".to_owned()),
+            ("VB_Exposed".to_owned(), "False".to_owned()),
+        ]
+    );
+    assert_eq!(rest, "Option Explicit\r\n");
+}
+
+#[test]
+fn module_attributes() {
+    const SOURCE: &[u8] = b"Attribute VB_Name = \"a\"\r\nSub A()\r\nEnd Sub\r\n";
+    let dir = dir_stream(&[("a", "a", 0)]);
+    let module = compress_literals(SOURCE);
+    let raw = project_container(&dir, &[("/VBA/a", &module)]);
+    let project = open_project(raw).unwrap();
+
+    assert_eq!(
+        project.module_attributes("a").unwrap(),
+        [("VB_Name".to_owned(), "a".to_owned())]
+    );
+    assert_eq!(
+        project.module_source_clean("a").unwrap(),
+        "Sub A()\r\nEnd Sub\r\n"
+    );
+    assert_eq!(project.module_source("a").unwrap().as_bytes(), SOURCE);
+}
+
+#[test]
+fn open_with_container() {
+    let nested_dir = compress_literals(&dir_stream(&[("b", "b", 0)]));
+    let raw = project_container(&dir_stream(&[]), &[("/Macros/VBA/dir", &nested_dir)]);
+    let container = CompoundFile::open(Cursor::new(raw)).unwrap();
+    let roots = Project::discover_projects(&container).unwrap();
+    assert!(roots.contains(&Path::new("/Macros").to_string_lossy().into_owned()));
+
+    let project = open_project_with_container(container, Path::new("/Macros")).unwrap();
+    assert_eq!(project.module_names().collect::<Vec<_>>(), ["b"]);
+}
+
+#[test]
+fn encoding_error() {
+    assert!(matches!(
+        cp_to_string(b"A", 0),
+        Err(Error::Encoding { code_page: 0 })
+    ));
+
+    // The code page is validated when opening a project. It can be changed afterwards.
+    const SOURCE: &[u8] = b"Attribute VB_Name = \"a\"\r\n";
+    let dir = dir_stream(&[("a", "a", 0)]);
+    let module = compress_literals(SOURCE);
+    let raw = project_container(&dir, &[("/VBA/a", &module)]);
+    let mut project = open_project(raw).unwrap();
+    project.information.code_page = 0;
+    assert!(matches!(
+        project.module_source("a"),
+        Err(Error::Encoding { code_page: 0 })
+    ));
+}
+
+#[test]
+fn fallback_encoding() {
+    const SOURCE: &[u8] = b"Sub \xc4()\r\n";
+    let mut dir = dir_stream(&[("a", "a", 0)]);
+    // Replace the `PROJECTCODEPAGE` record's code page with one that cannot be mapped.
+    let record = b"\x03\x00\x02\x00\x00\x00\xe4\x04";
+    let pos = dir.windows(record.len()).position(|w| w == record).unwrap();
+    dir[pos + 6..pos + 8].copy_from_slice(&0_u16.to_le_bytes());
+    let module = compress_literals(SOURCE);
+    let raw = project_container(&dir, &[("/VBA/a", &module)]);
+
+    assert!(matches!(
+        open_project(raw.clone()),
+        Err(Error::Encoding { code_page: 0 })
+    ));
+
+    let project = OpenOptions::new()
+        .fallback_encoding(true)
+        .open(raw)
+        .unwrap();
+    assert_eq!(
+        project.warnings(),
+        [Warning::EncodingFallback {
+            offset: pos,
+            code_page: 0,
+            fallback: 1252
+        }]
+    );
+    assert_eq!(project.information.code_page, 1252);
+    assert_eq!(project.module_source("a").unwrap(), "Sub Ä()\r\n");
+}
+
+#[test]
+fn cache_sources() {
+    let dir = dir_stream(&[("a", "a", 0)]);
+    let module = compress_literals(b"Sub A()");
+    let raw = project_container(&dir, &[("/VBA/a", &module)]);
+    let replace_module = |project: &mut Project| {
+        project
+            .container
+            .get_mut()
+            .as_mut()
+            .unwrap()
+            .create_stream("/VBA/a")
+            .unwrap()
+            .write_all(&compress_literals(b"Sub B()"))
+            .unwrap();
+    };
+
+    // Without caching, every call reads the module stream.
+    let mut project = open_project(raw.clone()).unwrap();
+    assert_eq!(project.module_source("a").unwrap(), "Sub A()");
+    replace_module(&mut project);
+    assert_eq!(project.module_source("a").unwrap(), "Sub B()");
+
+    // With caching, the first result is served until the project is reloaded.
+    let mut project = OpenOptions::new().cache_sources(true).open(raw).unwrap();
+    assert_eq!(project.module_source("a").unwrap(), "Sub A()");
+    replace_module(&mut project);
+    assert_eq!(project.module_source("a").unwrap(), "Sub A()");
+    project.reload().unwrap();
+    assert_eq!(project.module_source("a").unwrap(), "Sub B()");
+}
+
+#[test]
+fn read_into_buffer() {
+    let dir = dir_stream(&[("a", "a", 0), ("b", "b", 0)]);
+    let module_a = compress_literals(b"Sub A()");
+    let module_b = compress(&[b'B'; 5000]);
+    let raw = project_container(&dir, &[("/VBA/a", &module_a), ("/VBA/b", &module_b)]);
+
+    for cache_sources in [false, true] {
+        let project = OpenOptions::new()
+            .cache_sources(cache_sources)
+            .open(raw.clone())
+            .unwrap();
+        let mut buffer = b"stale".to_vec();
+
+        project.read_stream_into("VBA/a", &mut buffer).unwrap();
+        assert_eq!(buffer, module_a);
+
+        project
+            .decompress_stream_into("VBA/b", 0, &mut buffer)
+            .unwrap();
+        assert_eq!(buffer, [b'B'; 5000]);
+        let capacity = buffer.capacity();
+        project
+            .decompress_stream_into("VBA/a", 0, &mut buffer)
+            .unwrap();
+        assert_eq!(buffer, b"Sub A()");
+        assert_eq!(buffer.capacity(), capacity);
+
+        assert!(project.read_stream_into("VBA/c", &mut buffer).is_err());
+    }
+}
+
+#[test]
+fn max_decompressed_size() {
+    // Three identically encoded chunks, each decompressing to 4096 bytes.
+    let container = compress(&[b'A'; 3 * 4096]);
+    assert_eq!(
+        decompress_bounded(&container, 3 * 4096).unwrap().1,
+        [b'A'; 3 * 4096]
+    );
+    let err = decompress_bounded(&container, 2 * 4096).unwrap_err();
+    let chunk_len = (container.len() - 1) / 3;
+    assert!(matches!(
+        decompressor_error(&container, err),
+        Error::Decompressor { offset } if offset == 1 + 2 * chunk_len
+    ));
+
+    let dir = dir_stream(&[("a", "a", 0)]);
+    let raw = project_container(&dir, &[("/VBA/a", &container)]);
+    let project = open_project(raw.clone()).unwrap();
+    assert_eq!(project.module_source("a").unwrap().len(), 3 * 4096);
+
+    let project = OpenOptions::new()
+        .max_decompressed_size(4096)
+        .open(raw.clone())
+        .unwrap();
+    assert!(matches!(
+        project.module_source("a"),
+        Err(Error::Module { source, .. })
+            if matches!(*source, Error::Decompressor { .. })
+    ));
+    assert!(project.decompress_stream_from("VBA/a", 0).is_err());
+
+    // The limit applies to the *dir* stream as well.
+    assert!(matches!(
+        OpenOptions::new().max_decompressed_size(16).open(raw),
+        Err(Error::Decompressor { .. })
+    ));
+}
+
+#[test]
+fn streaming_decompressor() {
+    let data = (0..10_000).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+    let container = compress_literals(&data);
+
+    // Reading in small, odd-sized steps crosses chunk boundaries.
+    let mut decompressor = Decompressor::new(container.as_slice());
+    let mut result = Vec::new();
+    let mut buf = [0_u8; 7];
+    loop {
+        let len = decompressor.read(&mut buf).unwrap();
+        if len == 0 {
+            break;
+        }
+        result.extend_from_slice(&buf[..len]);
+    }
+    assert_eq!(result, decompress(&container).unwrap().1);
+
+    // Malformed input is reported with its offset into the container.
+    let decompressor_offset = |container: &[u8]| {
+        let err = Decompressor::new(container)
+            .read_to_end(&mut Vec::new())
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        match err.into_inner().unwrap().downcast::<Error>().map(|e| *e) {
+            Ok(Error::Decompressor { offset }) => offset,
+            e => panic!("unexpected error: {:?}", e),
+        }
+    };
+    assert_eq!(decompressor_offset(b""), 0);
+    assert_eq!(decompressor_offset(b"\x02\x00\xB0\x00"), 0);
+    assert_eq!(decompressor_offset(b"\x01"), 1);
+    // The third chunk's header declares more data than remains.
+    assert_eq!(decompressor_offset(&container[..container.len() - 1]), 8197);
+}
+
+#[test]
+fn copy_token_in_second_chunk() {
+    // CopyToken offset and length bits depend on the position relative to the start of
+    // the chunk, not the start of the decompressed stream.
+    let mut container = compress(&[b'Z'; 4096]);
+    // Compressed chunk: LiteralToken 'A', LiteralToken 'B', CopyToken (offset 2,
+    // length 4). With 4 offset bits, the token is (2 - 1) << 12 | (4 - 3).
+    container.extend_from_slice(&[0x04, 0xb0, 0b0000_0100, b'A', b'B', 0x01, 0x10]);
+
+    let expected = [&[b'Z'; 4096][..], b"ABABAB"].concat();
+    assert_eq!(decompress(&container).unwrap().1, expected);
+
+    let mut output = Vec::new();
+    Decompressor::new(&container[..])
+        .read_to_end(&mut output)
+        .unwrap();
+    assert_eq!(output, expected);
+}
+
+#[test]
+fn multi_chunk_dir_stream() {
+    let references = (0..20)
+        .map(|n| control_reference(&format!("Control{}", n), &[n as u8; 16]))
+        .collect::<Vec<_>>()
+        .concat();
+    let names = (0..200).map(|n| format!("Module{}", n)).collect::<Vec<_>>();
+    let modules = names
+        .iter()
+        .map(|name| (name.as_str(), name.as_str(), 0))
+        .collect::<Vec<_>>();
+    let dir = dir_stream_with_references(&references, &modules);
+
+    // The decompressed *dir* stream spans several chunks, and its records straddle chunk
+    // boundaries.
+    let (_, decompressed) = decompress(&compress(&dir)).unwrap();
+    assert_eq!(decompressed, dir);
+    assert!(decompressed.len() > 4 * 4096);
+
+    let (remainder, information) = parse_project_information(&decompressed).unwrap();
+    assert!(remainder.is_empty());
+    assert_eq!(information.references.len(), 20);
+    assert_eq!(information.modules.len(), 200);
+    assert_eq!(information.modules[199].name, "Module199");
+
+    let project = open_project(project_container(&dir, &[])).unwrap();
+    assert!(project.module_names().eq(names.iter().map(String::as_str)));
+}
+
+#[test]
+fn write_to() {
+    let dir = dir_stream(&[("a", "a", 0)]);
+    let module = compress(b"Sub A()");
+    let raw = project_container_with_version(
+        Version::V4,
+        &dir,
+        &[("/VBA/a", &module), ("/PROJECT", b"ID=\"{}\"")],
+    );
+    // An unmodified project is copied verbatim, and remains usable.
+    let mut project = open_project(raw.clone()).unwrap();
+    let mut written = Cursor::new(Vec::new());
+    project.write_to(&mut written).unwrap();
+    assert_eq!(written.into_inner(), raw);
+    assert_eq!(project.module_source("a").unwrap(), "Sub A()");
+
+    project
+        .container
+        .get_mut()
+        .as_mut()
+        .unwrap()
+        .set_state_bits("/VBA", 0x1234)
+        .unwrap();
+    project.set_module_flags("a", true, false).unwrap();
+
+    let mut written = Cursor::new(Vec::new());
+    project.write_to(&mut written).unwrap();
+
+    let copy = open_project(written.into_inner()).unwrap();
+    assert_eq!(copy.module_source("a").unwrap(), "Sub A()");
+    assert!(copy.modules[0].read_only);
+    assert_eq!(copy.read_stream("/PROJECT").unwrap(), b"ID=\"{}\"");
+    assert_eq!(copy.list().unwrap(), project.list().unwrap());
+    copy.with_container(|container| {
+        assert_eq!(container.version(), Version::V4);
+        assert_eq!(container.entry("/VBA").unwrap().state_bits(), 0x1234);
+    });
+}
+
+#[test]
+fn entries() {
+    let dir = dir_stream(&[("a", "a", 0)]);
+    let module = compress(b"Sub A()");
+    let before = std::time::SystemTime::now() - std::time::Duration::from_secs(1);
+    let raw = project_container(&dir, &[("/VBA/a", &module)]);
+    let project = open_project(raw).unwrap();
+
+    let entries = project.entries().unwrap();
+    let list = entries
+        .iter()
+        .map(|entry| (entry.name.clone(), entry.path.clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(list, project.list().unwrap());
+
+    let stream = entries
+        .iter()
+        .find(|entry| Path::new(&entry.path) == Path::new("/VBA/a"))
+        .unwrap();
+    assert!(stream.is_stream);
+    assert_eq!(stream.len, module.len() as u64);
+    assert_eq!(stream.clsid, "{00000000-0000-0000-0000-000000000000}");
+
+    let storage = entries.iter().find(|entry| entry.name == "VBA").unwrap();
+    assert!(!storage.is_stream);
+    assert!(storage.created >= before);
+    assert!(storage.modified >= before);
+}
+
+#[test]
+fn designer_storage() {
+    let dir = dir_stream(&[("UserForm1", "UserForm1", 0), ("Module1", "Module1", 0)]);
+    let raw = project_container(
+        &dir,
+        &[
+            ("/UserForm1/\u{1}CompObj", b"compobj"),
+            ("/UserForm1/\u{3}VBFrame", b"VERSION 5.00"),
+            ("/UserForm1/f", b"form"),
+            ("/UserForm1/i07/f", b"frame"),
+        ],
+    );
+    let project = open_project(raw).unwrap();
+
+    let mut streams = project.designer_storage("userform1").unwrap();
+    streams.sort();
+    assert_eq!(
+        streams,
+        [
+            ("\u{1}CompObj".to_owned(), b"compobj".to_vec()),
+            ("\u{3}VBFrame".to_owned(), b"VERSION 5.00".to_vec()),
+            ("f".to_owned(), b"form".to_vec()),
+            ("i07/f".to_owned(), b"frame".to_vec()),
+        ]
+    );
+
+    assert!(matches!(
+        project.designer_storage("Module1"),
+        Err(Error::Cfb(_))
+    ));
+    assert!(matches!(
+        project.designer_storage("Missing"),
+        Err(Error::ModuleNotFound(_))
+    ));
+}
+
+#[test]
+fn project_properties() {
+    const PROJECT: &[u8] = b"ID=\"{917DED8F-4C5B-4F3A-8D0B-FA8F6A0BD4F3}\"\r\n\
+        Document=ThisDocument/&H00000000\r\n\
+        Module=Module2\r\n\
+        Class=Class1\r\n\
+        Module=Module1\r\n\
+        BaseClass=UserForm1\r\n\
+        HelpFile=\"\"\r\n\
+        Name=\"Proj\xe9kt\"\r\n\
+        HelpContextID=\"42\"\r\n\
+        CMG=\"0305A1\"\r\n\
+        \r\n\
+        [Host Extender Info]\r\n\
+        &H00000001={3832D640-CF90-11CF-8E43-00A0C911005A};VBE;&H00000000\r\n\
+        &H00000002=malformed\r\n\
+        \r\n\
+        [Workspace]\r\n\
+        Module=0, 0, 0, 0, C\r\n";
+
+    let dir = dir_stream(&[]);
+    let raw = project_container(&dir, &[("/PROJECT", PROJECT)]);
+    let project = open_project(raw).unwrap();
+    let properties = project.project_properties().unwrap();
+
+    assert_eq!(
+        properties.id.as_deref(),
+        Some("{917DED8F-4C5B-4F3A-8D0B-FA8F6A0BD4F3}")
+    );
+    assert_eq!(properties.name.as_deref(), Some("Projékt"));
+    assert_eq!(properties.help_file.as_deref(), Some(""));
+    assert_eq!(properties.help_context_id, Some(42));
+    assert_eq!(properties.protection_state.as_deref(), Some("0305A1"));
+    assert_eq!(properties.password, None);
+    assert_eq!(
+        properties.modules,
+        ["ThisDocument", "Module2", "Class1", "Module1", "UserForm1"]
+    );
+    assert_eq!(
+        properties.host_extenders,
+        [HostExtender {
+            index: 1,
+            guid: "{3832D640-CF90-11CF-8E43-00A0C911005A}".to_owned(),
+            lib_name: "VBE".to_owned(),
+            flags: 0,
+        }]
+    );
+
+    // The stream is optional as far as the *dir* stream parser is concerned.
+    let project = open_project(project_container(&dir, &[])).unwrap();
+    assert!(matches!(project.project_properties(), Err(Error::Cfb(_))));
+}
+
+#[test]
+fn clone_metadata() {
+    let references = control_reference("ctl", &[1; 16]);
+    let dir = dir_stream_with_references(&references, &[("a", "a", 0), ("b", "b", 0)]);
+    let project = open_project(project_container(&dir, &[])).unwrap();
+
+    let metadata = project.clone_metadata();
+    drop(project);
+    assert_eq!(metadata.information.name(), "VBAProject");
+    assert_eq!(metadata.references.len(), 1);
+    assert_eq!(metadata.references[0].name(), Some("ctl"));
+    let names = metadata.modules.iter().map(|module| module.name.as_str());
+    assert!(names.eq(["a", "b"]));
+}
+
+#[test]
+fn project_across_threads() {
+    fn assert_send<T: Send>() {}
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send::<Project>();
+    assert_send::<Project<std::fs::File>>();
+    assert_send_sync::<super::ProjectMetadata>();
+
+    let dir = dir_stream(&[("a", "a", 0)]);
+    let module = compress_literals(b"Sub A()\r\nEnd Sub\r\n");
+    let project = open_project(project_container(&dir, &[("/VBA/a", &module)])).unwrap();
+    let source = std::thread::spawn(move || project.module_source("a").unwrap())
+        .join()
+        .unwrap();
+    assert_eq!(source, "Sub A()\r\nEnd Sub\r\n");
+
+    let project = open_project(project_container(&dir, &[("/VBA/a", &module)])).unwrap();
+    let (metadata, mut container) = project.into_parts();
+    assert_eq!(metadata.modules[0].stream_name, "a");
+    assert!(container.open_stream("/VBA/a").is_ok());
+}
+
+#[test]
+fn name_map() {
+    let dir = dir_stream(&[]);
+    let project_wm = [
+        &b"Module1\0"[..],
+        &[
+            b'M', 0, b'o', 0, b'd', 0, b'u', 0, b'l', 0, b'e', 0, b'1', 0, 0, 0,
+        ],
+        b"?\0",
+        &[0xe5, 0x65, 0x2c, 0x67, 0, 0],
+        b"\0\0",
+    ]
+    .concat();
+    let raw = project_container(&dir, &[("/VBA/PROJECTwm", &project_wm)]);
+    let project = open_project(raw).unwrap();
+    assert_eq!(
+        project.name_map().unwrap(),
+        [
+            ("Module1".to_owned(), "Module1".to_owned()),
+            ("?".to_owned(), "日本".to_owned())
+        ]
+    );
+
+    // Missing terminator
+    let raw = project_container(&dir, &[("/VBA/PROJECTwm", &project_wm[..24])]);
+    let project = open_project(raw).unwrap();
+    assert!(matches!(
+        project.name_map(),
+        Err(Error::Parser {
+            offset: 24,
+            kind: ParseErrorKind::UnexpectedEof,
+            record: None
+        })
+    ));
+}
+
+#[test]
+fn vba_project_stream() {
+    let dir = dir_stream(&[]);
+    let raw = project_container(
+        &dir,
+        &[("/VBA/_VBA_PROJECT", b"\xcc\x61\xb2\x00\x00\x03\x00\xaa\xbb")],
+    );
+    let project = open_project(raw).unwrap();
+    assert_eq!(project.vba_version().unwrap(), 0xb2);
+    assert_eq!(project.performance_cache().unwrap(), b"\xaa\xbb");
+
+    let raw = project_container(&dir, &[("/VBA/_VBA_PROJECT", b"\xcc\x62\xb2\x00")]);
+    let project = open_project(raw).unwrap();
+    assert!(matches!(
+        project.vba_version(),
+        Err(Error::Parser {
+            offset: 0,
+            kind: ParseErrorKind::UnexpectedTag,
+            record: None
+        })
+    ));
+}
+
+#[test]
+fn signature() {
+    let dir = dir_stream(&[]);
+    let project = open_project(project_container(&dir, &[])).unwrap();
+    assert!(!project.is_signed());
+    assert_eq!(project.signature().unwrap(), None);
+
+    let raw = project_container(
+        &dir,
+        &[
+            ("/\u{5}DigitalSignature", b"legacy"),
+            ("/\u{5}DigitalSignatureExt", b"v3"),
+        ],
+    );
+    let project = open_project(raw).unwrap();
+    assert!(project.is_signed());
+    assert_eq!(project.signature().unwrap().unwrap(), b"v3");
+}
+
+#[test]
+fn protection() {
+    let dir = dir_stream(&[]);
+    let open = |properties: String| {
+        let raw = project_container(&dir, &[("/PROJECT", properties.as_bytes())]);
+        open_project(raw).unwrap()
+    };
+
+    // Missing properties
+    let project = open(String::new());
+    assert_eq!(project.protection().unwrap(), ProtectionState::default());
+
+    // Unprotected
+    let project = open(format!(
+        "CMG=\"{}\"\r\nDPB=\"{}\"\r\nGC=\"{}\"\r\n",
+        encrypt(0x0E, 0xCF, &[0x00, 0x00, 0x00, 0x00]),
+        encrypt(0x37, 0xCF, &[0x00]),
+        encrypt(0x90, 0xCF, &[0xFF]),
+    ));
+    assert_eq!(project.protection().unwrap(), ProtectionState::default());
+
+    // Locked for viewing, with a password hash
+    let project = open(format!(
+        "CMG=\"{}\"\r\nDPB=\"{}\"\r\nGC=\"{}\"\r\n",
+        encrypt(0x0E, 0xCF, &[0x01, 0x00, 0x00, 0x00]),
+        encrypt(0x37, 0xCF, &[0xFF; 29]),
+        encrypt(0x96, 0xCF, &[0x00]),
+    ));
+    assert_eq!(
+        project.protection().unwrap(),
+        ProtectionState {
+            locked: true,
+            view_protected: true,
+            password_protected: true,
+            ..ProtectionState::default()
+        }
+    );
+
+    // Malformed data
+    let project = open("GC=\"0F0D\"\r\n".to_owned());
+    assert!(matches!(
+        project.protection(),
+        Err(Error::Parser {
+            offset: 2,
+            kind: ParseErrorKind::UnexpectedEof,
+            record: None
+        })
+    ));
+    let project = open("GC=\"0F0E00\"\r\n".to_owned());
+    assert!(matches!(
+        project.protection(),
+        Err(Error::Parser {
+            offset: 1,
+            kind: ParseErrorKind::UnexpectedValue,
+            record: None
+        })
+    ));
+    let project = open(format!("GC=\"{}00\"\r\n", encrypt(0x96, 0xCF, &[0x00])));
+    assert!(matches!(
+        project.protection(),
+        Err(Error::Parser {
+            offset: 11,
+            kind: ParseErrorKind::TrailingData,
+            record: None
+        })
+    ));
+}
+
+#[test]
+fn source_cache_consistency() {
+    const VBA_PROJECT: &[u8] = b"\xcc\x61\xb2\x00\x00\x03\x00";
+    let source = compress(b"Attribute VB_Name = \"a\"\r\nSub A()\r\nEnd Sub\r\n");
+    let stomped = compress(b"Attribute VB_Name = \"a\"\r\n\r\n");
+
+    // No compiled code
+    let dir = dir_stream(&[("a", "a", 0)]);
+    let raw = project_container(
+        &dir,
+        &[("/VBA/_VBA_PROJECT", VBA_PROJECT), ("/VBA/a", &source)],
+    );
+    let project = open_project(raw).unwrap();
+    assert!(!project.has_performance_cache().unwrap());
+    assert_eq!(
+        project.source_cache_consistency().unwrap(),
+        CacheStatus::SourceOnly
+    );
+
+    // Compiled code ahead of the source code
+    let dir = dir_stream(&[("a", "a", 4)]);
+    let module = [&b"pcod"[..], &source].concat();
+    let raw = project_container(
+        &dir,
+        &[("/VBA/_VBA_PROJECT", VBA_PROJECT), ("/VBA/a", &module)],
+    );
+    let project = open_project(raw).unwrap();
+    assert_eq!(
+        project.source_cache_consistency().unwrap(),
+        CacheStatus::CachePresent
+    );
+
+    // Compiled code with the source code removed
+    let module = [&b"pcod"[..], &stomped].concat();
+    let vba_project = [VBA_PROJECT, b"cache"].concat();
+    let raw = project_container(
+        &dir,
+        &[("/VBA/_VBA_PROJECT", &vba_project), ("/VBA/a", &module)],
+    );
+    let project = open_project(raw).unwrap();
+    assert!(project.has_performance_cache().unwrap());
+    assert_eq!(
+        project.source_cache_consistency().unwrap(),
+        CacheStatus::SourceMissing(vec!["a".to_owned()])
+    );
+}
+
+#[test]
+fn module_kind() {
+    const PROJECT: &[u8] = b"Module=Module1\r\n\
+        Document=ThisDocument/&H00000000\r\n\
+        Class=Class1\r\n\
+        BaseClass=UserForm1\r\n";
+
+    let mut dir = dir_stream(&[
+        ("Module1", "Module1", 0),
+        ("ThisDocument", "ThisDocument", 0),
+        ("Class1", "Class1", 0),
+        ("UserForm1", "UserForm1", 0),
+        ("Unlisted", "Unlisted", 0),
+    ]);
+    // Turn all but the first module into DocClsDesigner modules.
+    const PROCEDURAL: &[u8] = b"\x21\x00\x00\x00\x00\x00\x2b\x00";
+    let positions = dir
+        .windows(PROCEDURAL.len())
+        .enumerate()
+        .filter(|(_, window)| *window == PROCEDURAL)
+        .map(|(position, _)| position)
+        .collect::<Vec<_>>();
+    for position in &positions[1..] {
+        dir[*position] = 0x22;
+    }
+
+    let raw = project_container(&dir, &[("/PROJECT", PROJECT)]);
+    let project = open_project(raw).unwrap();
+    let kinds = project
+        .module_names()
+        .map(|name| project.module_kind(name).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        kinds,
+        [
+            ModuleKind::Procedural,
+            ModuleKind::Document,
+            ModuleKind::Class,
+            ModuleKind::Designer,
+            ModuleKind::Class
+        ]
+    );
+    assert!(matches!(
+        project.module_kind("Module2"),
+        Err(Error::ModuleNotFound(_))
+    ));
+
+    let extensions = kinds.iter().map(ModuleKind::extension).collect::<Vec<_>>();
+    assert_eq!(extensions, ["bas", "cls", "cls", "frm", "cls"]);
+    let extensions = project
+        .modules
+        .iter()
+        .map(|module| module.module_type.extension())
+        .collect::<Vec<_>>();
+    assert_eq!(extensions, ["bas", "cls", "cls", "cls", "cls"]);
+}
+
+#[test]
+fn sources() {
+    let dir = dir_stream(&[("a", "a", 0), ("b", "b", 0), ("c", "c", 0)]);
+    let module_a = compress(b"Sub A()");
+    let module_c = compress(b"Sub C()");
+    let raw = project_container(&dir, &[("/VBA/a", &module_a), ("/VBA/c", &module_c)]);
+    let project = open_project(raw).unwrap();
+
+    let sources = project.sources().collect::<Vec<_>>();
+    assert_eq!(sources.len(), 3);
+    assert!(
+        matches!(&sources[0], Ok((module, source)) if module.name == "a" && source == "Sub A()")
+    );
+    assert!(matches!(&sources[1], Err(Error::Module { name, .. }) if name == "b"));
+    assert!(
+        matches!(&sources[2], Ok((module, source)) if module.name == "c" && source == "Sub C()")
+    );
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn sources_par() {
+    let dir = dir_stream(&[("a", "a", 0), ("b", "b", 0), ("c", "c", 0)]);
+    let module_a = compress(b"Sub A()");
+    let module_c = compress(b"Sub C()");
+    let raw = project_container(&dir, &[("/VBA/a", &module_a), ("/VBA/c", &module_c)]);
+    let project = open_project(raw).unwrap();
+
+    let sources = project.sources_par();
+    assert_eq!(sources.len(), 3);
+    assert!(matches!(&sources[0], Ok((name, source)) if name == "a" && source == "Sub A()"));
+    assert!(matches!(&sources[1], Err(Error::Module { name, .. }) if name == "b"));
+    assert!(matches!(&sources[2], Ok((name, source)) if name == "c" && source == "Sub C()"));
+}
+
+#[cfg(feature = "zip")]
+#[test]
+fn to_source_zip() {
+    let dir = dir_stream(&[("Module1", "Module1", 0)]);
+    let module = compress(b"Attribute VB_Name = \"Module1\"\r\nSub A()\r\nEnd Sub\r\n");
+    let raw = project_container(&dir, &[("/VBA/Module1", &module)]);
+    let project = open_project(raw).unwrap();
+
+    let archive = project.to_source_zip().unwrap();
+    let mut archive = zip::ZipArchive::new(Cursor::new(archive)).unwrap();
+    let mut read = |name: &str| {
+        let mut contents = String::new();
+        archive
+            .by_name(name)
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        contents
+    };
+    assert_eq!(read("src/Module1.bas"), "Sub A()\r\nEnd Sub\r\n");
+    let manifest: serde_json::Value = serde_json::from_str(&read("project.json")).unwrap();
+    assert_eq!(manifest["information"]["name"], "VBAProject");
+    assert_eq!(manifest["references"], serde_json::json!([]));
+}
+
+#[cfg(feature = "zip")]
+#[test]
+fn to_source_zip_file_names() {
+    // Module names that aren't valid file names, and a class module without a *PROJECT*
+    // stream to tell its kind
+    let mut dir = dir_stream(&[("../../x", "x", 0), ("a\\b\n", "ab", 0)]);
+    const PROCEDURAL: &[u8] = b"\x21\x00\x00\x00\x00\x00\x2b\x00";
+    let position = dir
+        .windows(PROCEDURAL.len())
+        .rposition(|window| window == PROCEDURAL)
+        .unwrap();
+    dir[position] = 0x22;
+    let module = compress(b"Sub A()");
+    let raw = project_container(&dir, &[("/VBA/x", &module), ("/VBA/ab", &module)]);
+    let project = open_project(raw).unwrap();
+
+    let archive = project.to_source_zip().unwrap();
+    let archive = zip::ZipArchive::new(Cursor::new(archive)).unwrap();
+    let mut names = archive.file_names().collect::<Vec<_>>();
+    names.sort_unstable();
+    assert_eq!(names, ["project.json", "src/.._.._x.bas", "src/a_b_.cls"]);
+}
+
+#[test]
+fn module_attributes_typed() {
+    const SOURCE: &[u8] = b"Attribute VB_Name = \"Class1\"\r\n\
+        Attribute VB_Base = \"0{FCFB3D2A-A0FA-1068-A738-08002B3371B5}\"\r\n\
+        Attribute VB_GlobalNameSpace = False\r\n\
+        Attribute VB_Creatable = False\r\n\
+        Attribute VB_PredeclaredId = True\r\n\
+        Attribute VB_Exposed = true\r\n\
+        Attribute VB_Customizable = -1\r\n\
+        Attribute VB_Description = \"A class\"\r\n\
+        Option Explicit\r\n";
+    let dir = dir_stream(&[("Class1", "Class1", 0)]);
+    let module = compress(SOURCE);
+    let raw = project_container(&dir, &[("/VBA/Class1", &module)]);
+    let project = open_project(raw).unwrap();
+
+    let attributes = project.modules[0].attributes(&project).unwrap();
+    assert_eq!(attributes.name.as_deref(), Some("Class1"));
+    assert_eq!(
+        attributes.base.as_deref(),
+        Some("0{FCFB3D2A-A0FA-1068-A738-08002B3371B5}")
+    );
+    assert!(!attributes.global_name_space);
+    assert!(!attributes.creatable);
+    assert!(attributes.predeclared_id);
+    assert!(attributes.exposed);
+    assert!(!attributes.customizable);
+    assert_eq!(attributes.other.len(), 2);
+    assert_eq!(attributes.other["VB_Customizable"], "-1");
+    assert_eq!(attributes.other["VB_Description"], "A class");
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_impls() {
+    fn serialize<T: serde::Serialize>() {}
+    fn deserialize<T: serde::de::DeserializeOwned>() {}
+
+    serialize::<Project>();
+    serialize::<super::DirRecord<'_>>();
+    deserialize::<super::ProjectMetadata>();
+    deserialize::<super::ModuleAttributes>();
+    deserialize::<super::ProjectProperties>();
+    deserialize::<Warning>();
+    deserialize::<ModuleKind>();
+
+    // Round-trip the data types through JSON.
+    fn roundtrip<T>(value: &T)
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned + PartialEq + std::fmt::Debug,
+    {
+        let json = serde_json::to_string(value).unwrap();
+        assert_eq!(
+            &serde_json::from_str::<T>(&json).unwrap(),
+            value,
+            "{}",
+            json
+        );
+    }
+
+    let mut references = control_reference("ctl", &[1; 16]);
+    // REFERENCEREGISTERED
+    references.extend(b"\x0d\x00\x0d\x00\x00\x00\x03\x00\x00\x00*\\G\0\0\0\0\0\0");
+    let dir = dir_stream_with_references(&references, &[("a", "a", 0), ("b", "b", 4)]);
+    let mut project = open_project(project_container(&dir, &[])).unwrap();
+    project.set_module_flags("b", true, true).unwrap();
+    assert_eq!(project.references.len(), 2);
+
+    roundtrip(&project.information);
+    roundtrip(&project.references);
+    roundtrip(&project.modules);
+    roundtrip(&project.clone_metadata());
+}
+
+#[test]
+fn summary() {
+    let references = control_reference("ctl", &[1; 16]);
+    let dir = dir_stream_with_references(&references, &[("a", "a", 0), ("b", "b", 0)]);
+    let raw = project_container(
+        &dir,
+        &[("/VBA/_VBA_PROJECT", b"\xcc\x61\xb2\x00\x00\x03\x00")],
+    );
+    let project = open_project(raw).unwrap();
+
+    assert_eq!(
+        project.summary().to_string(),
+        "Name:        VBAProject\n\
+         Platform:    Win32\n\
+         Code page:   1252\n\
+         VBA version: 0x00B2\n\
+         Modules:     2 (2 procedural, 0 document/class/designer)\n\
+         References:  1\n  \
+         *\\G{00000000-0000-0000-0000-000000000000}#0.0#0##"
+    );
+
+    let project = open_project(project_container(&dir, &[])).unwrap();
+    assert_eq!(project.summary().vba_version, None);
+}
+
+#[test]
+fn find_module_case_insensitive() {
+    let dir = dir_stream(&[("Module1", "Module1", 0)]);
+    let module = compress(b"Sub A()");
+    let raw = project_container(&dir, &[("/VBA/Module1", &module)]);
+    let project = open_project(raw).unwrap();
+
+    assert_eq!(project.find_module("mODULE1").unwrap().name, "Module1");
+    assert!(project.module_exists("module1"));
+    assert_eq!(project.module_source("MODULE1").unwrap(), "Sub A()");
+    assert!(matches!(
+        project.module_source_raw("Module2"),
+        Err(Error::ModuleNotFound(name)) if name == "Module2"
+    ));
+}
+
+#[test]
+fn stream_cache() {
+    let dir = dir_stream(&[("a", "a", 0)]);
+    let module = compress(b"Sub A()");
+    let raw = project_container(&dir, &[("/VBA/a", &module)]);
+    let mut project = OpenOptions::new().cache_sources(true).open(raw).unwrap();
+    assert_eq!(project.module_source_raw("a").unwrap(), b"Sub A()");
+
+    project
+        .container
+        .get_mut()
+        .as_mut()
+        .unwrap()
+        .create_stream("/VBA/a")
+        .unwrap()
+        .write_all(&compress(b"Sub B()"))
+        .unwrap();
+    assert_eq!(project.module_source_raw("a").unwrap(), b"Sub A()");
+    assert_eq!(project.read_stream("VBA/a").unwrap(), module);
+
+    project.clear_cache();
+    assert_eq!(project.module_source_raw("a").unwrap(), b"Sub B()");
+}
+
+#[test]
+fn module_cookie() {
+    let mut dir = dir_stream(&[("a", "a", 0), ("b", "b", 0)]);
+    // Replace the second module's cookie.
+    const COOKIE: &[u8] = b"\x2c\x00\x02\x00\x00\x00\xff\xff";
+    let position = dir
+        .windows(COOKIE.len())
+        .rposition(|window| window == COOKIE)
+        .unwrap();
+    dir[position + 6..position + 8].copy_from_slice(&0x1234_u16.to_le_bytes());
+
+    let project = open_project(project_container(&dir, &[])).unwrap();
+    assert_eq!(project.modules[0].cookie(), 0xffff);
+    assert_eq!(project.modules[1].cookie(), 0x1234);
+}
+
+#[test]
+fn open_project_borrowed() {
+    let dir = dir_stream(&[("a", "a", 0)]);
+    let module = compress(b"Sub A()");
+    let raw = project_container(&dir, &[("/VBA/a", &module)]);
+
+    let project = open_project_bytes(&raw).unwrap();
+    assert_eq!(project.module_source("a").unwrap(), "Sub A()");
+}
+
+#[test]
+fn project_diff() {
+    let a = compress(b"Sub A()");
+    let b = compress(b"Sub B()");
+    let changed = compress(b"Sub B2()");
+    let references = b"\x0D\x00\x19\x00\x00\x00\x0F\x00\x00\x00*\\G{00020430}#2\
+        \x00\x00\x00\x00\x00\x00";
+
+    let dir = dir_stream(&[("a", "a", 0), ("b", "b", 0)]);
+    let old = open_project(project_container(&dir, &[("/VBA/a", &a), ("/VBA/b", &b)])).unwrap();
+    assert!(old.diff(&old).unwrap().is_empty());
+
+    // Moving the source code within the stream doesn't count as a modification.
+    let mut moved = vec![0_u8; 4];
+    moved.extend_from_slice(&a);
+    let dir = dir_stream_with_references(references, &[("A", "a", 4), ("c", "c", 0)]);
+    let new = open_project(project_container(
+        &dir,
+        &[("/VBA/a", &moved), ("/VBA/c", &changed)],
+    ))
+    .unwrap();
+
+    let diff = old.diff(&new).unwrap();
+    assert!(!diff.is_empty());
+    assert!(!diff.information_changed);
+    assert_eq!(diff.removed_modules, ["b"]);
+    assert_eq!(diff.added_modules, ["c"]);
+    // Module names match case-insensitively, but the name is part of the metadata.
+    assert_eq!(diff.modified_modules.len(), 1);
+    assert_eq!(diff.modified_modules[0].name, "a");
+    assert!(diff.modified_modules[0].metadata_changed);
+    assert_eq!(diff.modified_modules[0].source, None);
+    assert_eq!(diff.added_references, new.references);
+    assert!(diff.removed_references.is_empty());
+
+    let dir = dir_stream(&[("a", "a", 0), ("b", "b", 0)]);
+    let edited = open_project(project_container(
+        &dir,
+        &[("/VBA/a", &a), ("/VBA/b", &changed)],
+    ))
+    .unwrap();
+    let diff = old.diff(&edited).unwrap();
+    assert_eq!(diff.modified_modules.len(), 1);
+    assert!(!diff.modified_modules[0].metadata_changed);
+    assert_eq!(
+        diff.modified_modules[0].source,
+        Some(("Sub B()".to_owned(), "Sub B2()".to_owned()))
+    );
+}
+
+#[test]
+fn raw_dir() {
+    let dir = dir_stream(&[("a", "a", 0)]);
+    let project = open_project(project_container(&dir, &[])).unwrap();
+    assert_eq!(project.raw_dir().unwrap(), dir);
+
+    // Corrupt the `PROJECTSYSKIND` record's value, so that parsing fails.
+    let mut corrupt = dir.clone();
+    corrupt[6] = 0x07;
+    let raw = project_container(&corrupt, &[]);
+    let offset = match open_project(raw.clone()) {
+        Err(err @ Error::Parser { .. }) => err.offset().unwrap(),
+        _ => panic!("expected a parser error"),
+    };
+    let raw_dir = raw_dir_from_reader(Cursor::new(raw)).unwrap();
+    assert_eq!(raw_dir, corrupt);
+    assert_eq!(raw_dir[offset..][..2], [0x01, 0x00]);
+
+    // The project is located in binary documents, too.
+    let raw = project_container(&[], &[("/Macros/VBA/dir", &compress_literals(&dir))]);
+    let mut container = CompoundFile::open(Cursor::new(raw)).unwrap();
+    container.remove_stream("/VBA/dir").unwrap();
+    container
+        .create_stream("/Macros/VBA/_VBA_PROJECT")
+        .unwrap()
+        .write_all(b"")
+        .unwrap();
+    container.flush().unwrap();
+    let raw = container.into_inner().into_inner();
+    assert_eq!(raw_dir_from_reader(Cursor::new(raw)).unwrap(), dir);
+}
+
+#[test]
+fn code_page_override() {
+    // "Sub 日本()" in Shift-JIS (code page 932), in a project declaring Windows-1252
+    const SOURCE: &[u8] = b"Sub \x93\xfa\x96\x7b()";
+    let dir = dir_stream(&[("a", "a", 0)]);
+    let raw = project_container(&dir, &[("/VBA/a", &compress(SOURCE))]);
+
+    let project = open_project(raw.clone()).unwrap();
+    assert_eq!(project.information.code_page, 1252);
+    assert_ne!(project.module_source("a").unwrap(), "Sub 日本()");
+
+    let project = OpenOptions::new()
+        .code_page(Some(932))
+        .open(raw.clone())
+        .unwrap();
+    assert_eq!(project.information.code_page, 932);
+    assert_eq!(project.module_source("a").unwrap(), "Sub 日本()");
+
+    assert!(matches!(
+        OpenOptions::new()
+            .code_page(Some(0))
+            .fallback_encoding(true)
+            .open(raw),
+        Err(Error::Encoding { code_page: 0 })
+    ));
+}
+
+#[test]
+fn observer_events() {
+    fn collect(raw: Vec<u8>) -> (Vec<String>, bool) {
+        let mut events = Vec::new();
+        let result = open_project_with_observer(raw, &mut |event| {
+            events.push(match event {
+                ParseEvent::DirDecompressed { len } => format!("dir {}", len),
+                ParseEvent::ReferencesParsed { count } => format!("references {}", count),
+                ParseEvent::ModuleParsed { name } => format!("module {}", name),
+            })
+        });
+        (events, result.is_ok())
+    }
+
+    let dir = dir_stream(&[("a", "a", 0), ("b", "b", 0)]);
+    let raw = project_container(&dir, &[("/VBA/a", &[]), ("/VBA/b", &[])]);
+    let expected = [
+        format!("dir {}", dir.len()),
+        "references 0".to_owned(),
+        "module a".to_owned(),
+        "module b".to_owned(),
+    ];
+    assert_eq!(collect(raw), (expected.to_vec(), true));
+
+    // Events preceding a parse error are delivered.
+    let truncated = &dir[..dir.len() - 8];
+    let raw = project_container(truncated, &[("/VBA/a", &[]), ("/VBA/b", &[])]);
+    assert_eq!(
+        collect(raw),
+        (
+            vec![
+                format!("dir {}", truncated.len()),
+                "references 0".to_owned(),
+                "module a".to_owned(),
+            ],
+            false
+        )
+    );
+}
+
+#[test]
+fn rename_module() {
+    const SOURCE: &[u8] = b"Attribute VB_Name = \"a\"\r\nSub A()\r\nEnd Sub\r\n";
+    const PROJECT: &[u8] = b"ID=\"{00000000-0000-0000-0000-000000000000}\"\r\n\
+        Module=a\r\nClass=b\r\nName=\"VBAProject\"\r\n\r\n\
+        [Workspace]\r\na=0, 0, 0, 0, C\r\nb=0, 0, 0, 0, C\r\n";
+    const PROJECT_WM: &[u8] = b"a\0a\0\0\0b\0b\0\0\0\0\0";
+    let dir = dir_stream(&[("a", "a", 4), ("b", "b", 0)]);
+    let module_a = [&b"\x01\x02\x03\x04"[..], &compress(SOURCE)].concat();
+    let raw = project_container(
+        &dir,
+        &[
+            ("/VBA/a", &module_a),
+            ("/VBA/b", &compress(b"Attribute VB_Name = \"b\"\r\n")),
+            ("/VBA/_VBA_PROJECT", b"\xcc\x61\xb5\x00\x00\x01\x00\xab"),
+            ("/VBA/PROJECTwm", PROJECT_WM),
+            ("/PROJECT", PROJECT),
+        ],
+    );
+    let mut project = open_project(raw).unwrap();
+
+    assert!(matches!(
+        project.rename_module("b", "A"),
+        Err(Error::DuplicateModule(name)) if name == "A"
+    ));
+    assert!(matches!(
+        project.rename_module("c", "d"),
+        Err(Error::ModuleNotFound(name)) if name == "c"
+    ));
+    assert!(matches!(
+        project.rename_module("b", "x/y"),
+        Err(Error::InvalidStreamName(name)) if name == "x/y"
+    ));
+    assert_eq!(project.vba_version().unwrap(), 0xb5);
+
+    project.rename_module("A", "Renamed").unwrap();
+    project.rename_module("b", "B").unwrap();
+    assert_eq!(
+        project.module_source("Renamed").unwrap(),
+        "Attribute VB_Name = \"Renamed\"\r\nSub A()\r\nEnd Sub\r\n"
+    );
+
+    let mut raw = Cursor::new(Vec::new());
+    project.write_to(&mut raw).unwrap();
+    let project = open_project(raw.into_inner()).unwrap();
+    let modules = project
+        .modules
+        .iter()
+        .map(|module| {
+            (
+                module.name.as_str(),
+                module.stream_name.as_str(),
+                module.text_offset,
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(modules, [("Renamed", "Renamed", 0), ("B", "B", 0)]);
+    assert_eq!(
+        project.module_source("Renamed").unwrap(),
+        "Attribute VB_Name = \"Renamed\"\r\nSub A()\r\nEnd Sub\r\n"
+    );
+    assert_eq!(
+        project.module_source("B").unwrap(),
+        "Attribute VB_Name = \"B\"\r\n"
+    );
+    assert!(!project.with_container(|container| container.is_stream("/VBA/a")));
+    assert_eq!(
+        String::from_utf8(project.read_stream("PROJECT").unwrap()).unwrap(),
+        "ID=\"{00000000-0000-0000-0000-000000000000}\"\r\n\
+        Module=Renamed\r\nClass=B\r\nName=\"VBAProject\"\r\n\r\n\
+        [Workspace]\r\nRenamed=0, 0, 0, 0, C\r\nB=0, 0, 0, 0, C\r\n"
+    );
+    assert_eq!(
+        project.name_map().unwrap(),
+        [
+            ("Renamed".to_owned(), "Renamed".to_owned()),
+            ("B".to_owned(), "B".to_owned())
+        ]
+    );
+    assert_eq!(project.vba_version().unwrap(), 0xffff);
+}
+
+#[test]
+fn rename_module_stream_collision() {
+    let dir = dir_stream(&[("A", "A", 0), ("B", "X", 0)]);
+    let raw = project_container(
+        &dir,
+        &[
+            ("/VBA/A", &compress(b"Sub A()")),
+            ("/VBA/X", &compress(b"Sub B()")),
+            ("/VBA/__SRP_0", b""),
+        ],
+    );
+    let mut project = open_project(raw).unwrap();
+
+    for new in ["X", "x", "dir", "__SRP_0", "_vba_project"] {
+        assert!(
+            matches!(
+                project.rename_module("A", new),
+                Err(Error::DuplicateModule(name)) if name == new
+            ),
+            "{}",
+            new
+        );
+    }
+    assert_eq!(project.module_source("A").unwrap(), "Sub A()");
+    assert_eq!(project.module_source("B").unwrap(), "Sub B()");
+
+    // Renaming a module to its own stream name is fine.
+    let dir = dir_stream(&[("A", "S", 0)]);
+    let raw = project_container(&dir, &[("/VBA/S", &compress(b"Sub A()"))]);
+    let mut project = open_project(raw).unwrap();
+    project.rename_module("A", "s").unwrap();
+    assert_eq!(project.module_source("s").unwrap(), "Sub A()");
+}
+
+#[test]
+fn rename_module_unsupported() {
+    let dir = dir_stream(&[("A", "A", 0)]);
+    let streams: &[(&str, &[u8])] = &[("/VBA/A", &compress(b"Sub A()"))];
+
+    // Code page override
+    let raw = project_container(&dir, streams);
+    let mut project = OpenOptions::new().code_page(Some(932)).open(raw).unwrap();
+    assert!(matches!(
+        project.rename_module("A", "B"),
+        Err(Error::Unsupported(_))
+    ));
+
+    // Fallback encoding
+    let mut fallback_dir = dir.clone();
+    let record = b"\x03\x00\x02\x00\x00\x00\xe4\x04";
+    let pos = fallback_dir
+        .windows(record.len())
+        .position(|w| w == record)
+        .unwrap();
+    fallback_dir[pos + 6..pos + 8].copy_from_slice(&0_u16.to_le_bytes());
+    let raw = project_container(&fallback_dir, streams);
+    let mut project = OpenOptions::new()
+        .fallback_encoding(true)
+        .open(raw)
+        .unwrap();
+    assert!(matches!(
+        project.rename_module("A", "B"),
+        Err(Error::Unsupported(_))
+    ));
+
+    // Designer module, with its designer storage
+    let raw = project_container(&dir, &[streams[0], ("/A/f", b"")]);
+    let mut project = open_project(raw).unwrap();
+    assert!(matches!(
+        project.rename_module("A", "B"),
+        Err(Error::Unsupported(_))
+    ));
+    assert_eq!(project.modules[0].name, "A");
+
+    // Designer module, with a stream name that differs from its name
+    let dir = dir_stream(&[("A", "UserForm1", 0)]);
+    let raw = project_container(
+        &dir,
+        &[
+            ("/VBA/UserForm1", &compress(b"Sub A()")),
+            ("/UserForm1/f", b""),
+        ],
+    );
+    let mut project = open_project(raw).unwrap();
+    assert!(matches!(
+        project.rename_module("A", "B"),
+        Err(Error::Unsupported(_))
+    ));
+    assert_eq!(project.modules[0].name, "A");
 }
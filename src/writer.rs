@@ -0,0 +1,320 @@
+#![forbid(unsafe_code)]
+
+use crate::{Error, Information, Module, ModuleType, ProjectMetadata, Reference, Result, SysKind};
+use alloc::{borrow::Cow, string::String, vec::Vec};
+use codepage::to_encoding;
+use encoding_rs::Encoding;
+
+/// A builder for *dir* streams.
+///
+/// Serializes a project's [`Information`], references, and modules into the record
+/// layout described in \[MS-OVBA\] section 2.3.4.2, as read by [`parse_dir`]. The
+/// result is the decompressed *dir* stream. Use [`compress`] to produce the stream's
+/// contents.
+///
+/// Records that duplicate information are written as mandated by the specification:
+/// Unicode records hold the UTF-16 encoding of their MBCS counterparts, and
+/// `PROJECTHELPFILEPATH` holds both copies of the help file path. Values that the
+/// specification requires readers to ignore (such as `PROJECTCOOKIE`) are written with
+/// their prescribed default. Parsing a *dir* stream, and building it again, thus
+/// reproduces the input, unless it holds such values, or optional records in a
+/// non-standard form.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # #[cfg(feature = "std")]
+/// # fn main() -> ovba::Result<()> {
+/// use std::fs::read;
+/// use ovba::{open_project, DirBuilder};
+///
+/// let data = read("vbaProject.bin")?;
+/// let project = open_project(data)?;
+/// let dir = DirBuilder::from(project.clone_metadata()).build()?;
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "std"))]
+/// # fn main() {}
+/// ```
+///
+/// [`parse_dir`]: crate::parse_dir
+/// [`compress`]: crate::compress
+#[derive(Clone, Debug)]
+pub struct DirBuilder {
+    information: Information,
+    references: Vec<Reference>,
+    modules: Vec<Module>,
+}
+
+impl DirBuilder {
+    /// Creates a builder for a project described by `information`, without references or
+    /// modules.
+    pub fn new(information: Information) -> Self {
+        Self {
+            information,
+            references: Vec::new(),
+            modules: Vec::new(),
+        }
+    }
+
+    /// Appends a reference.
+    pub fn reference(&mut self, reference: Reference) -> &mut Self {
+        self.references.push(reference);
+        self
+    }
+
+    /// Appends a module.
+    pub fn module(&mut self, module: Module) -> &mut Self {
+        self.modules.push(module);
+        self
+    }
+
+    /// Serializes the *dir* stream.
+    ///
+    /// Strings are encoded using the project's code page. A module's doc string that
+    /// cannot be represented in the code page is stored in its Unicode record only, as
+    /// done by Office.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error::Encoding`] error if the code page cannot be mapped to an
+    /// encoding, or any other string cannot be represented in the code page.
+    pub fn build(&self) -> Result<Vec<u8>> {
+        let info = &self.information;
+        let code_page = info.code_page;
+        let encoding = to_encoding(code_page).ok_or(Error::Encoding { code_page })?;
+        let encode = |s: &str| encode(encoding, code_page, s);
+        let mut w = Writer::default();
+
+        // PROJECTINFORMATION Record
+        let sys_kind: u32 = match info.sys_kind {
+            SysKind::Win16 => 0,
+            SysKind::Win32 => 1,
+            SysKind::MacOs => 2,
+            SysKind::Win64 => 3,
+        };
+        w.record(0x01, &sys_kind.to_le_bytes());
+        if let Some(compat) = info.compat {
+            w.record(0x4a, &compat.to_le_bytes());
+        }
+        w.record(0x02, &info.lcid.to_le_bytes());
+        w.record(0x14, &info.lcid_invoke.to_le_bytes());
+        w.record(0x03, &code_page.to_le_bytes());
+        w.record(0x04, &encode(&info.name)?);
+        w.record(0x05, &encode(&info.doc_string)?);
+        w.record(0x40, &utf16(&info.doc_string));
+        let help_file = encode(&info.help_file_1)?;
+        w.record(0x06, &help_file);
+        w.record(0x3d, &help_file);
+        w.record(0x07, &info.help_context.to_le_bytes());
+        w.record(0x08, &info.lib_flags.to_le_bytes());
+        // PROJECTVERSION's Reserved field holds 4, but the record's data is 6 bytes.
+        w.u16(0x09)
+            .u32(4)
+            .u32(info.version_major)
+            .u16(info.version_minor);
+        if let Some(constants) = &info.constants {
+            w.record(0x0c, &encode(constants)?);
+            w.record(0x3c, &utf16(constants));
+        }
+
+        // PROJECTREFERENCES Record
+        for reference in &self.references {
+            write_reference(&mut w, reference, &encode)?;
+        }
+
+        // PROJECTMODULES Record
+        w.record(0x0f, &(self.modules.len() as u16).to_le_bytes());
+        w.record(0x13, &0xffff_u16.to_le_bytes());
+        for module in &self.modules {
+            w.record(0x19, &encode(&module.name)?);
+            w.record(0x47, &utf16(&module.name));
+            w.record(0x1a, &encode(&module.stream_name)?);
+            w.record(0x32, &utf16(&module.stream_name));
+            let doc_string = encode(&module.doc_string).unwrap_or_default();
+            w.record(0x1c, &doc_string);
+            w.record(0x48, &utf16(&module.doc_string));
+            w.record(0x31, &(module.text_offset as u32).to_le_bytes());
+            w.record(0x1e, &module.help_context.to_le_bytes());
+            w.record(0x2c, &module.cookie.to_le_bytes());
+            match module.module_type {
+                ModuleType::Procedural => w.record(0x21, &[]),
+                ModuleType::DocClsDesigner => w.record(0x22, &[]),
+            };
+            if module.read_only {
+                w.record(0x25, &[]);
+            }
+            if module.private {
+                w.record(0x28, &[]);
+            }
+            w.record(0x2b, &[]);
+        }
+
+        // Terminator
+        w.record(0x10, &[]);
+
+        Ok(w.buffer)
+    }
+}
+
+/// Writes a REFERENCE record, encoding MBCS strings using `encode`.
+fn write_reference(
+    w: &mut Writer,
+    reference: &Reference,
+    encode: &dyn Fn(&str) -> Result<Vec<u8>>,
+) -> Result<()> {
+    let write_name = |w: &mut Writer, name: &Option<String>| -> Result<()> {
+        if let Some(name) = name {
+            w.record(0x16, &encode(name)?);
+            w.record(0x3e, &utf16(name));
+        }
+        Ok(())
+    };
+
+    match reference {
+        Reference::Control(control) => {
+            write_name(w, &control.name)?;
+            if let Some(libid_original) = &control.libid_original {
+                w.record(0x33, &encode(libid_original)?);
+            }
+            let twiddled = Writer::default()
+                .sized(&encode(&control.libid_twiddled)?)
+                .u32(0)
+                .u16(0)
+                .take();
+            w.record(0x2f, &twiddled);
+            // The extended type library is omitted by some producers, see
+            // `parse_reference_control`.
+            let has_extended = control.name_extended.is_some()
+                || !control.libid_extended.is_empty()
+                || control.guid != [0; 16]
+                || control.cookie != 0;
+            if has_extended {
+                write_name(w, &control.name_extended)?;
+                let extended = Writer::default()
+                    .sized(&encode(&control.libid_extended)?)
+                    .u32(0)
+                    .u16(0)
+                    .bytes(&control.guid)
+                    .u32(control.cookie)
+                    .take();
+                w.record(0x30, &extended);
+            }
+        }
+        Reference::Original(original) => {
+            write_name(w, &original.name)?;
+            w.record(0x33, &encode(&original.libid_original)?);
+        }
+        Reference::Registered(registered) => {
+            write_name(w, &registered.name)?;
+            let data = Writer::default()
+                .sized(&encode(&registered.libid)?)
+                .u32(0)
+                .u16(0)
+                .take();
+            w.record(0x0d, &data);
+        }
+        Reference::Project(project) => {
+            write_name(w, &project.name)?;
+            let data = Writer::default()
+                .sized(&encode(&project.libid_absolute)?)
+                .sized(&encode(&project.libid_relative)?)
+                .u32(project.major_version)
+                .u16(project.minor_version)
+                .take();
+            w.record(0x0e, &data);
+        }
+    }
+    Ok(())
+}
+
+impl From<ProjectMetadata> for DirBuilder {
+    fn from(metadata: ProjectMetadata) -> Self {
+        Self {
+            information: metadata.information,
+            references: metadata.references,
+            modules: metadata.modules,
+        }
+    }
+}
+
+/// Accumulates little-endian encoded fields.
+#[derive(Default)]
+struct Writer {
+    buffer: Vec<u8>,
+}
+
+impl Writer {
+    /// Writes a record in the common `Id` (`u16`), `Size` (`u32`), `Data` layout.
+    ///
+    /// Records without data (such as `MODULEREADONLY`) are written with a `Size` of 0,
+    /// which matches the records' `Reserved` fields.
+    fn record(&mut self, id: u16, data: &[u8]) -> &mut Self {
+        self.u16(id).sized(data)
+    }
+
+    /// Writes `data`, preceded by its size as a `u32`.
+    fn sized(&mut self, data: &[u8]) -> &mut Self {
+        self.u32(data.len() as u32).bytes(data)
+    }
+
+    fn u16(&mut self, value: u16) -> &mut Self {
+        self.bytes(&value.to_le_bytes())
+    }
+
+    fn u32(&mut self, value: u32) -> &mut Self {
+        self.bytes(&value.to_le_bytes())
+    }
+
+    fn bytes(&mut self, data: &[u8]) -> &mut Self {
+        self.buffer.extend_from_slice(data);
+        self
+    }
+
+    fn take(&mut self) -> Vec<u8> {
+        core::mem::take(&mut self.buffer)
+    }
+}
+
+/// Serializes the contents of a *PROJECTwm* stream from pairs of MBCS and Unicode
+/// module names, as returned by `parse_name_map`.
+#[cfg(feature = "std")]
+pub(crate) fn write_name_map(name_map: &[(String, String)], code_page: u16) -> Result<Vec<u8>> {
+    let mut w = Writer::default();
+    for (name, name_unicode) in name_map {
+        w.bytes(&encode_cp(name, code_page)?)
+            .bytes(&[0x00])
+            .bytes(&utf16(name_unicode))
+            .u16(0x0000);
+    }
+    w.u16(0x0000);
+    Ok(w.buffer)
+}
+
+/// Encodes `s` using the encoding that corresponds to `code_page`.
+///
+/// Returns an [`Error::Encoding`] error if the code page cannot be mapped to an encoding,
+/// or `s` cannot be represented in the code page.
+#[cfg(feature = "std")]
+pub(crate) fn encode_cp(s: &str, code_page: u16) -> Result<Vec<u8>> {
+    let encoding = to_encoding(code_page).ok_or(Error::Encoding { code_page })?;
+    encode(encoding, code_page, s)
+}
+
+/// Encodes `s` using `encoding`, failing if `s` cannot be represented.
+fn encode(encoding: &'static Encoding, code_page: u16, s: &str) -> Result<Vec<u8>> {
+    let (result, output_encoding, had_errors) = encoding.encode(s);
+    // UTF-16 encodings cannot be encoded into, and produce UTF-8 instead.
+    if had_errors || output_encoding != encoding {
+        return Err(Error::Encoding { code_page });
+    }
+    Ok(match result {
+        Cow::Borrowed(result) => result.to_vec(),
+        Cow::Owned(result) => result,
+    })
+}
+
+/// Encodes `s` as UTF-16LE.
+fn utf16(s: &str) -> Vec<u8> {
+    s.encode_utf16().flat_map(u16::to_le_bytes).collect()
+}
@@ -0,0 +1,92 @@
+//! Crate-level round-trip tests.
+//!
+//! These tests write a complete VBA project container, open it through the public API,
+//! and verify that the data comes back unchanged. This exercises the *dir* stream writer
+//! and parser, the compressor and decompressor, and CFB access together.
+//!
+//! The *dir* stream is built using [`ovba::DirBuilder`], and streams are encoded using
+//! [`ovba::compress`]. The container is assembled using the `cfb` crate.
+
+#![cfg(feature = "std")]
+
+use cfb::CompoundFile;
+use ovba::{compress, open_project, DirBuilder, Information, Module, ModuleType};
+use std::io::{Cursor, Write};
+
+/// Builds an empty project, with a single procedural module holding `source`.
+fn build_project(module_name: &str, source: &str) -> Vec<u8> {
+    let dir = DirBuilder::new(Information::new("VBAProject"))
+        .module(Module::new(module_name, ModuleType::Procedural))
+        .build()
+        .unwrap();
+
+    let mut container = CompoundFile::create(Cursor::new(Vec::new())).unwrap();
+    container.create_storage("/VBA").unwrap();
+    container
+        .create_stream("/VBA/dir")
+        .unwrap()
+        .write_all(&compress(&dir))
+        .unwrap();
+    container
+        .create_stream(format!("/VBA/{}", module_name))
+        .unwrap()
+        .write_all(&compress(source.as_bytes()))
+        .unwrap();
+    container.flush().unwrap();
+    container.into_inner().into_inner()
+}
+
+/// Returns source code for a module called `name`, spanning several compressed chunks.
+fn module_source(name: &str) -> String {
+    format!(
+        "Attribute VB_Name = \"{}\"\r\n{}",
+        name,
+        "Sub Main()\r\n    MsgBox \"Hello, world!\"\r\nEnd Sub\r\n".repeat(200)
+    )
+}
+
+#[test]
+fn roundtrip_module_source() {
+    let source = module_source("Module1");
+    let raw = build_project("Module1", &source);
+
+    let project = open_project(raw).unwrap();
+    assert_eq!(project.information, Information::new("VBAProject"));
+    assert_eq!(
+        project.modules,
+        [Module::new("Module1", ModuleType::Procedural)]
+    );
+    assert_eq!(project.module_source("Module1").unwrap(), source);
+    assert_eq!(
+        project.module_attributes("Module1").unwrap(),
+        [("VB_Name".to_owned(), "Module1".to_owned())]
+    );
+}
+
+#[test]
+fn roundtrip_rename_module() {
+    let raw = build_project("Module1", &module_source("Module1"));
+
+    let mut project = open_project(raw).unwrap();
+    project.rename_module("Module1", "Renamed").unwrap();
+    let mut written = Cursor::new(Vec::new());
+    project.write_to(&mut written).unwrap();
+
+    let project = open_project(written.into_inner()).unwrap();
+    assert_eq!(
+        project.modules,
+        [Module::new("Renamed", ModuleType::Procedural)]
+    );
+    assert_eq!(
+        project.module_source("Renamed").unwrap(),
+        module_source("Renamed")
+    );
+    assert_eq!(
+        project.module_attributes("Renamed").unwrap(),
+        [("VB_Name".to_owned(), "Renamed".to_owned())]
+    );
+    assert!(matches!(
+        project.module_source("Module1"),
+        Err(ovba::Error::ModuleNotFound(_))
+    ));
+}